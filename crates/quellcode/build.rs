@@ -1,3 +1,111 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    env,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use syntect::{
+    dumps::{dump_binary, dump_to_file},
+    highlighting::{Theme, ThemeSet},
+};
+
+const SYNTAX_DIR: &str = "data/syntaxes";
+const THEME_DIR: &str = "data/themes";
+
 fn main() {
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR is set by cargo"));
+
+    println!("cargo:rerun-if-changed={SYNTAX_DIR}");
+    println!("cargo:rerun-if-changed={THEME_DIR}");
+
+    let syntaxes_hash = compile_syntaxes(&out_dir.join("syntaxes.packdump"));
+    println!("cargo:rustc-env=QUELLCODE_SYNTAXES_HASH={syntaxes_hash:016x}");
+    write_syntax_licenses(&out_dir.join("syntax-licenses.md"));
+
+    compile_themes(&out_dir.join("themes.themedump"));
+
     tauri_build::build()
 }
+
+/// Compiles bat's syntaxes, as packaged by two-face, together with the syntaxes in [SYNTAX_DIR]
+/// and returns the hash of the dump.
+fn compile_syntaxes(dump: &Path) -> u64 {
+    // NOTE: two-face includes syntect's default syntaxes, except for JavaDoc.
+    let mut builder = two_face::syntax::extra_no_newlines().into_builder();
+    builder
+        .add_from_folder(SYNTAX_DIR, false)
+        .expect("Failed to load bundled syntaxes");
+
+    let bytes = dump_binary(&builder.build());
+    std::fs::write(dump, &bytes).expect("Failed to dump bundled syntaxes");
+
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Writes the licenses of the syntaxes from two-face, which have to be shipped with the app.
+fn write_syntax_licenses(path: &Path) {
+    let mut licenses = String::from("# Syntax licenses\n\n");
+
+    for license in two_face::acknowledgement::listing().for_syntaxes() {
+        license.write_md(&mut licenses);
+    }
+
+    std::fs::write(path, licenses).expect("Failed to write syntax licenses");
+}
+
+/// Compiles the themes in [THEME_DIR], named like the app names themes loaded at runtime.
+fn compile_themes(dump: &Path) {
+    let mut theme_set = ThemeSet::new();
+
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(THEME_DIR)
+        .expect("Failed to read bundled themes")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let file_name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let (name, theme) = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("sublime-color-scheme") => {
+                let scheme = sublime_color_scheme::parse_color_scheme_file(&path)
+                    .unwrap_or_else(|err| panic!("Failed to parse {}: {err}", path.display()));
+                let name = scheme.name.clone().unwrap_or(file_name);
+
+                (
+                    name,
+                    Theme::try_from(scheme).expect("Failed to convert theme"),
+                )
+            }
+            Some("json") => {
+                let theme = syntect_vscode::parse_vscode_theme_file(&path)
+                    .unwrap_or_else(|err| panic!("Failed to parse {}: {err}", path.display()));
+                let name = theme.name.clone().unwrap_or(file_name);
+
+                (
+                    name,
+                    Theme::try_from(theme).expect("Failed to convert theme"),
+                )
+            }
+            Some("tmTheme") => {
+                let theme = ThemeSet::get_theme(&path)
+                    .unwrap_or_else(|err| panic!("Failed to parse {}: {err}", path.display()));
+                let name = theme.name.clone().unwrap_or(file_name);
+
+                (name, theme)
+            }
+            _ => continue,
+        };
+
+        theme_set.themes.insert(name, theme);
+    }
+
+    dump_to_file(&theme_set, dump).expect("Failed to dump bundled themes");
+}
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Result of generating the snapshot of a single file of a batch.
+ */
+export type BatchOutput = { input: string, 
+/**
+ * The written files, two for separate light and dark variants
+ */
+outputs: Array<string>, 
+/**
+ * Whether the batch was cancelled while generating the file, nothing is written then
+ */
+cancelled: boolean, error?: string, };
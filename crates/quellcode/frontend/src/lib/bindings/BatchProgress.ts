@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Payload of the `batch-progress` event, sent before each file is processed.
+ */
+export type BatchProgress = { 
+/**
+ * The generator job of the batch, which can be cancelled
+ */
+jobId: number, current: number, total: number, input: string, };
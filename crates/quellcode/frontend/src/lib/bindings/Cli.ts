@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Command line arguments, applied by the frontend once it is ready.
+ */
+export type Cli = { 
+/**
+ * Source file to open
+ */
+file?: string, 
+/**
+ * Syntax of the file, detected from its name and content if omitted
+ */
+syntax?: string, 
+/**
+ * Name of a saved preset to load at startup
+ */
+preset?: string, };
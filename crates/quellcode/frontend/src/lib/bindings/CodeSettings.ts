@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Settings of the `[code]` table.
+ */
+export type CodeSettings = { 
+/**
+ * Theme used for syntax highlighting, editor, and generators
+ */
+theme: string, 
+/**
+ * Language syntax used for syntax highlighting
+ */
+syntax: string, fontFamily: string, fontSize: number, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PropertyError } from "./PropertyError";
+
+export type GenerateCodeError = { "kind": "notFound" } | { "kind": "invalidProperties", "details": Array<PropertyError> } | { "kind": "noVariants" };
@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type GeneratorEvent = { "kind": "started" } | { "kind": "cancelled" } | { "kind": "progress", message: string | null, step: number, };
+export type GeneratorEvent = { "kind": "started" } | { "kind": "cancelled" } | { "kind": "progress", message: string | null, step: number, } | { "kind": "finished", output: string, } | { "kind": "finishedVariants", light: string, dark: string, } | { "kind": "failed", error: string, };
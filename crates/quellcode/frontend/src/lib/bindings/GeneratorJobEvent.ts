@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GeneratorEvent } from "./GeneratorEvent";
+
+/**
+ * A [GeneratorEvent] tagged with the job it belongs to.
+ */
+export type GeneratorJobEvent = { jobId: number, } & GeneratorEvent;
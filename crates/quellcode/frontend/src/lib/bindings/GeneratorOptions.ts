@@ -1,4 +1,8 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { PropertyValue } from "./PropertyValue";
 
-export type GeneratorOptions = { fontSize: number, fontFamily: string, extra: { [key in string]?: PropertyValue }, };
+export type GeneratorOptions = { fontSize: number, 
+/**
+ * Expression that overrides `font_size`, e.g. `max(12, 600 / longest_line)`
+ */
+fontSizeExpression?: string, fontFamily: string, extra: { [key in string]?: PropertyValue }, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * An inclusive, one based range of lines.
+ */
+export type LineRange = { start: number, end: number, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PropertyValue } from "./PropertyValue";
+
+/**
+ * A saved combination of generator, theme, font and generator properties.
+ */
+export type Preset = { name: string, generator: string, theme: string, syntax?: string, fontFamily: string, fontSize: number, fontSizeExpression?: string, extra: { [key in string]?: PropertyValue }, };
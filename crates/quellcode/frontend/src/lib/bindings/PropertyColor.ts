@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A RGBA color used by [PropertyInfo::Color] properties.
+ */
+export type PropertyColor = { r: number, g: number, b: number, a: number, };
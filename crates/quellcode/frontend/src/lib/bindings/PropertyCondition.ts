@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PropertyValue } from "./PropertyValue";
+
+/**
+ * A condition on the value of another property.
+ *
+ * Can be parsed from strings such as `"include_background"`, `"!preserve_tabs"`,
+ * `"frame_style == macos"` or `"frame_style != none"`.
+ */
+export type PropertyCondition = { "kind": "enabled", property: string, } | { "kind": "disabled", property: string, } | { "kind": "equals", property: string, value: PropertyValue, } | { "kind": "notEquals", property: string, value: PropertyValue, };
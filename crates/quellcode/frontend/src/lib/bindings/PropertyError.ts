@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type PropertyError = { "kind": "unknownProperty", "details": string } | { "kind": "invalidProperty", "details": { property: string, source: PropertyError, } } | { "kind": "invalidValueType" } | { "kind": "belowMinimum", "details": { value: number, min: number, } } | { "kind": "aboveMaximum", "details": { value: number, max: number, } } | { "kind": "invalidStep", "details": { value: number, step: number, } } | { "kind": "invalidOption", "details": string } | { "kind": "invalidColor", "details": string } | { "kind": "invalidLineRange", "details": string } | { "kind": "invalidFileExtension", "details": string } | { "kind": "unknownFontFamily", "details": string } | { "kind": "invalidExpression", "details": { expression: string, message: string, } } | { "kind": "invalidCondition", "details": string };
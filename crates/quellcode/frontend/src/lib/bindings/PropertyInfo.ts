@@ -1,4 +1,19 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LineRange } from "./LineRange";
+import type { PropertyColor } from "./PropertyColor";
+import type { PropertyCondition } from "./PropertyCondition";
 import type { StringPropertySubtype } from "./StringPropertySubtype";
 
-export type PropertyInfo = { "kind": "string", name: string, description: string, default: string | null, subType: StringPropertySubtype | null, displayName: string | null, dependsOn: string | null, disables: string | null, } | { "kind": "integer", name: string, description: string, default: number | null, min: number | null, max: number | null, step: number | null, dependsOn: string | null, displayName: string | null, disables: string | null, } | { "kind": "float", name: string, description: string, default: number | null, min: number | null, max: number | null, step: number | null, dependsOn: string | null, displayName: string | null, disables: string | null, } | { "kind": "boolean", name: string, description: string, default: boolean | null, dependsOn: string | null, displayName: string | null, disables: string | null, };
+export type PropertyInfo = { "kind": "string", name: string, description: string, default: string | null, subType: StringPropertySubtype | null, displayName: string | null, dependsOn: Array<PropertyCondition>, disables: Array<string>, } | { "kind": "integer", name: string, description: string, default: number | null, min: number | null, max: number | null, step: number | null, dependsOn: Array<PropertyCondition>, displayName: string | null, disables: Array<string>, } | { "kind": "float", name: string, description: string, default: number | null, min: number | null, max: number | null, step: number | null, dependsOn: Array<PropertyCondition>, displayName: string | null, disables: Array<string>, } | { "kind": "boolean", name: string, description: string, default: boolean | null, dependsOn: Array<PropertyCondition>, displayName: string | null, disables: Array<string>, } | { "kind": "enum", name: string, description: string, default: string | null, options: Array<string>, dependsOn: Array<PropertyCondition>, displayName: string | null, disables: Array<string>, } | { "kind": "color", name: string, description: string, default: PropertyColor | null, 
+/**
+ * Whether the alpha channel can be edited
+ */
+alpha: boolean, dependsOn: Array<PropertyCondition>, displayName: string | null, disables: Array<string>, } | { "kind": "font", name: string, description: string, default: string | null, 
+/**
+ * Only list monospaced families
+ */
+monospace: boolean, dependsOn: Array<PropertyCondition>, displayName: string | null, disables: Array<string>, } | { "kind": "lineRanges", name: string, description: string, default: Array<LineRange> | null, dependsOn: Array<PropertyCondition>, displayName: string | null, disables: Array<string>, } | { "kind": "file", name: string, description: string, default: string | null, 
+/**
+ * Allowed file extensions without the leading dot, any file is allowed if empty
+ */
+extensions: Array<string>, dependsOn: Array<PropertyCondition>, displayName: string | null, disables: Array<string>, };
@@ -1,3 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LineRange } from "./LineRange";
+import type { PropertyColor } from "./PropertyColor";
 
-export type PropertyValue = string | number | number | boolean;
+export type PropertyValue = string | number | number | boolean | PropertyColor | Array<LineRange> | { expression: string, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FileError } from "./FileError";
+
+/**
+ * Payload of the `themes-changed` and `syntaxes-changed` events.
+ */
+export type ReloadEvent = { 
+/**
+ * Names of syntaxes or IDs of themes that were added or reloaded
+ */
+changed: Array<string>, removed: Array<string>, 
+/**
+ * Files that failed to load, previously loaded versions are kept
+ */
+errors: Array<FileError>, };
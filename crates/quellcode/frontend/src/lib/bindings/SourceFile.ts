@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DetectedSyntax } from "./DetectedSyntax";
+
+/**
+ * A source file opened for snapshotting.
+ */
+export type SourceFile = { path: string, 
+/**
+ * The selected lines of the file, or the whole file
+ */
+code: string, 
+/**
+ * Number of lines in the whole file
+ */
+lines: number, syntax?: DetectedSyntax, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type SourceLocation = { 
+/**
+ * 1-based line number
+ */
+line: number, 
+/**
+ * 1-based column number
+ */
+column: number, };
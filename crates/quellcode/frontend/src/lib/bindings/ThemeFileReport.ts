@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ThemeFileStatus } from "./ThemeFileStatus";
+import type { ThemeFormat } from "./ThemeFormat";
+
+export type ThemeFileReport = { path: string, format: ThemeFormat, status: ThemeFileStatus, };
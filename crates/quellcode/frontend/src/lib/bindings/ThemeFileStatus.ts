@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SourceLocation } from "./SourceLocation";
+
+export type ThemeFileStatus = { "status": "loaded", name: string, warnings: Array<string>, } | { "status": "failed", 
+/**
+ * The error and its causes, outermost first
+ */
+errors: Array<string>, location?: SourceLocation, } | { "status": "merged", into: string, };
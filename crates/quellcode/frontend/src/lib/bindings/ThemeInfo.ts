@@ -0,0 +1,21 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ThemeAppearance } from "./ThemeAppearance";
+import type { ThemeFormat } from "./ThemeFormat";
+import type { ThemeOrigin } from "./ThemeOrigin";
+
+/**
+ * Metadata of a theme in the [ThemeRegistry].
+ */
+export type ThemeInfo = { 
+/**
+ * Unique ID, e.g. `user:Monokai.tmTheme` or `bundled:Nord`
+ */
+id: string, name: string, author?: string, 
+/**
+ * Format of the theme file, bundled themes are precompiled
+ */
+format?: ThemeFormat, origin: ThemeOrigin, path?: string, appearance: ThemeAppearance, 
+/**
+ * Whether the theme is loaded from a `.hidden-color-scheme` or `.hidden-tmTheme` file
+ */
+hidden: boolean, };
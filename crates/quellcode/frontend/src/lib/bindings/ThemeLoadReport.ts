@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ThemeFileReport } from "./ThemeFileReport";
+
+/**
+ * Outcome of loading every theme file, sorted by path.
+ */
+export type ThemeLoadReport = { files: Array<ThemeFileReport>, };
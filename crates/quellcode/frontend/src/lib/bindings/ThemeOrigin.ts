@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Where a theme comes from, later origins take precedence when themes are looked up by name.
+ */
+export type ThemeOrigin = "bundled" | "store" | "user";
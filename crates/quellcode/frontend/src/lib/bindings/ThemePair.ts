@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A light and a dark theme, referred to by ID or name.
+ */
+export type ThemePair = { light: string, dark: string, };
@@ -1,5 +1,7 @@
+import type { ThemeInfo } from "@lib/bindings/ThemeInfo";
+
 export interface AppState {
-	themes: Array<string>;
+	themes: Array<ThemeInfo>;
 	syntaxes: Array<string>;
 }
 
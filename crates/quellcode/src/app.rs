@@ -1,50 +1,593 @@
-use std::sync::Mutex;
+use std::{collections::BTreeMap, path::PathBuf, sync::Mutex};
 
 use color_eyre::eyre::Result;
-use log::info;
-use tauri::State;
+use log::{info, warn};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+use thiserror::Error;
+use ts_rs::TS;
 
-use crate::{generator::GeneratorOptions, AppState};
+use crate::{
+    asset_store::{self, AssetData},
+    dir::{config_file, presets_file, store_theme_dir, theme_pairs_file},
+    generator::{generate_variants, GeneratorEvent, GeneratorOptions, JobId, VariantOutput},
+    preset::{Preset, PresetStore},
+    property::{active_properties, LineRange, PropertyError, PropertyInfo, PropertyValue},
+    scraping::github_api::GithubApi,
+    settings::Settings,
+    snapshot::{self, BatchOutput, BatchProgress, SourceFile},
+    syntax::{self, DetectedSyntax},
+    theme::{load_pairs, save_pairs, ThemeInfo, ThemeLoadReport, ThemePair},
+    watcher::FileError,
+    AppState,
+};
 
+#[derive(Debug, Error, Serialize, TS)]
+#[serde(rename_all = "camelCase", tag = "kind", content = "details")]
+#[ts(export)]
+pub enum GenerateCodeError {
+    #[error("Failed to find generator, syntax, or theme")]
+    NotFound,
+    #[error("Invalid generator properties")]
+    InvalidProperties(Vec<PropertyError>),
+    #[error("Failed to find a light and dark variant of the theme")]
+    NoVariants,
+}
+
+/// Starts a generation job and returns its id.
+///
+/// The output is delivered through a [GeneratorEvent::Finished] event tagged with the returned id.
+/// With `variants`, the light and dark variant of the theme are generated in one run, either as a
+/// single adaptive output or through a [GeneratorEvent::FinishedVariants] event.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn generate_code(
+    app_handle: AppHandle,
     state: State<'_, Mutex<AppState>>,
     code: String,
     generator_name: String,
     syntax_name: String,
     theme_name: String,
     options: GeneratorOptions,
-) -> Result<String, String> {
+    variants: Option<bool>,
+) -> Result<JobId, GenerateCodeError> {
     info!("Generating code with generator {}", generator_name);
 
-    let (syntax_set, syntect_themes, generators, context) = {
+    let variants = variants.unwrap_or_default();
+    let pairs = if variants {
+        load_pairs(&theme_pairs_file(&app_handle)).unwrap_or_else(|err| {
+            warn!("Failed to load theme pairs: {err}");
+            Vec::new()
+        })
+    } else {
+        Vec::new()
+    };
+
+    let (syntax_set, theme, pair, generators, jobs) = {
         let state = state.lock().expect("Failed to lock state");
         (
             state.syntect_syntaxes.clone(),
-            state.syntect_themes.themes.clone(),
+            state.themes.theme(&theme_name).cloned(),
+            variants
+                .then(|| state.themes.pair(&theme_name, &pairs))
+                .flatten()
+                .map(|(light, dark)| (light.theme.clone(), dark.theme.clone())),
             state.generators.clone(),
-            state.generator_context.clone(),
+            state.generator_jobs.clone(),
         )
     };
 
+    if variants && pair.is_none() {
+        return Err(GenerateCodeError::NoVariants);
+    }
+
     let syntax = syntax_set.find_syntax_by_name(&syntax_name).cloned();
-    let theme = syntect_themes.get(&theme_name).cloned();
     let generator = generators
         .iter()
-        .find_map(|(info, gen)| (info.name() == generator_name).then_some(gen))
+        .find(|(info, _)| info.name() == generator_name)
         .cloned();
 
-    if let (Some(syntax), Some(theme), Some(generator)) = (syntax, theme, generator) {
-        let result = tokio::task::spawn_blocking(move || {
-            generator
-                .generate_code(&code, &theme, &syntax, &syntax_set, &options, &context)
-                .map_err(|err| err.to_string())
-        })
+    if let (Some(syntax), Some(theme), Some((info, generator))) = (syntax, theme, generator) {
+        let options = info
+            .prepare_options(options, &code, &theme)
+            .map_err(GenerateCodeError::InvalidProperties)?;
+
+        let context = jobs.start();
+        let job_id = context.job_id;
+        let generation_context = context.clone();
+
+        let generation = tokio::task::spawn_blocking(move || {
+            let context = generation_context;
+            let result = match &pair {
+                Some((light, dark)) => generate_variants(
+                    generator.as_ref(),
+                    &code,
+                    light,
+                    dark,
+                    &syntax,
+                    &syntax_set,
+                    &options,
+                    &context,
+                )
+                .map(GeneratorEvent::from),
+                None => generator
+                    .generate_code(&code, &theme, &syntax, &syntax_set, &options, &context)
+                    .map(|output| GeneratorEvent::Finished { output }),
+            };
+
+            match result {
+                Ok(_) if context.is_cancelled() => None,
+                Ok(event) => Some(event),
+                Err(err) => Some(GeneratorEvent::Failed {
+                    error: err.to_string(),
+                }),
+            }
+        });
+
+        // NOTE: Awaited separately so the job also finishes when the generator panics.
+        tokio::task::spawn(async move {
+            let event = generation.await.unwrap_or_else(|err| {
+                Some(GeneratorEvent::Failed {
+                    error: err.to_string(),
+                })
+            });
+
+            if let Some(event) = event {
+                let _ = context.send(event);
+            }
+
+            jobs.finish(job_id);
+        });
+
+        Ok(job_id)
+    } else {
+        Err(GenerateCodeError::NotFound)
+    }
+}
+
+/// Requests cancellation of a running generation job.
+///
+/// Returns `false` if the job already finished or never existed.
+#[tauri::command]
+pub fn cancel_generation(state: State<'_, Mutex<AppState>>, job_id: JobId) -> bool {
+    info!("Cancelling generation job {}", job_id);
+
+    state
+        .lock()
+        .expect("Failed to lock state")
+        .generator_jobs
+        .cancel(job_id)
+}
+
+/// Returns the properties of a generator that are active for the given values.
+#[tauri::command]
+pub fn effective_properties(
+    state: State<'_, Mutex<AppState>>,
+    generator_name: String,
+    values: BTreeMap<String, PropertyValue>,
+) -> Result<Vec<PropertyInfo>, String> {
+    let state = state.lock().expect("Failed to lock state");
+    let (info, _) = state
+        .generators
+        .iter()
+        .find(|(info, _)| info.name() == generator_name)
+        .ok_or(format!("Could not find generator \"{}\"", generator_name))?;
+
+    let properties = info.properties();
+    let active = active_properties(properties, &values);
+
+    Ok(properties
+        .iter()
+        .filter(|property| active.contains(property.name()))
+        .cloned()
+        .collect())
+}
+
+/// Returns the values the generator declares properties for, used when switching generators.
+#[tauri::command]
+pub fn generator_options(
+    state: State<'_, Mutex<AppState>>,
+    generator_name: String,
+    values: BTreeMap<String, PropertyValue>,
+) -> Result<BTreeMap<String, PropertyValue>, String> {
+    let state = state.lock().expect("Failed to lock state");
+    let (info, _) = state
+        .generators
+        .iter()
+        .find(|(info, _)| info.name() == generator_name)
+        .ok_or(format!("Could not find generator \"{}\"", generator_name))?;
+
+    Ok(info.declared_options(values))
+}
+
+#[tauri::command]
+pub fn presets(app_handle: AppHandle) -> Result<Vec<Preset>, String> {
+    let store = PresetStore::open(presets_file(&app_handle)).map_err(|err| err.to_string())?;
+
+    store.list().map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+pub fn load_preset(app_handle: AppHandle, name: String) -> Result<Preset, String> {
+    let store = PresetStore::open(presets_file(&app_handle)).map_err(|err| err.to_string())?;
+
+    store.get(&name).map_err(|err| err.to_string())
+}
+
+/// Saves a preset, replacing an existing preset with the same name.
+#[tauri::command]
+pub fn save_preset(app_handle: AppHandle, preset: Preset) -> Result<(), String> {
+    info!("Saving preset {}", preset.name);
+
+    let mut store = PresetStore::open(presets_file(&app_handle)).map_err(|err| err.to_string())?;
+
+    store.save(&preset).map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+pub fn rename_preset(app_handle: AppHandle, name: String, new_name: String) -> Result<(), String> {
+    info!("Renaming preset {} to {}", name, new_name);
+
+    let mut store = PresetStore::open(presets_file(&app_handle)).map_err(|err| err.to_string())?;
+
+    store
+        .rename(&name, &new_name)
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+pub fn delete_preset(app_handle: AppHandle, name: String) -> Result<(), String> {
+    info!("Deleting preset {}", name);
+
+    let mut store = PresetStore::open(presets_file(&app_handle)).map_err(|err| err.to_string())?;
+
+    store.delete(&name).map_err(|err| err.to_string())
+}
+
+/// Imports all presets from a presets file and returns their names.
+#[tauri::command]
+pub fn import_presets(app_handle: AppHandle, path: PathBuf) -> Result<Vec<String>, String> {
+    info!("Importing presets from {}", path.display());
+
+    let mut store = PresetStore::open(presets_file(&app_handle)).map_err(|err| err.to_string())?;
+
+    store.import(&path).map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+pub fn export_preset(app_handle: AppHandle, name: String, path: PathBuf) -> Result<(), String> {
+    info!("Exporting preset {} to {}", name, path.display());
+
+    let store = PresetStore::open(presets_file(&app_handle)).map_err(|err| err.to_string())?;
+
+    store.export(&name, &path).map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+pub fn settings(state: State<'_, Mutex<AppState>>) -> Settings {
+    state.lock().expect("Failed to lock state").settings.clone()
+}
+
+/// Validates and saves the settings, returning them with unknown names replaced.
+#[tauri::command]
+pub fn save_settings(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    mut settings: Settings,
+) -> Result<Settings, String> {
+    let mut state = state.lock().expect("Failed to lock state");
+    settings.validate(&state.themes.keys(), &state.syntax_names());
+    settings
+        .save(&config_file(&app_handle))
+        .map_err(|err| err.to_string())?;
+
+    state.settings = settings.clone();
+
+    Ok(settings)
+}
+
+/// Detects the syntax of `code`, using the file name if the code was read from `path`.
+#[tauri::command]
+pub async fn detect_syntax(
+    state: State<'_, Mutex<AppState>>,
+    code: String,
+    path: Option<PathBuf>,
+) -> Result<Option<DetectedSyntax>, String> {
+    let syntax_set = state
+        .lock()
+        .expect("Failed to lock state")
+        .syntect_syntaxes
+        .clone();
+
+    tokio::task::spawn_blocking(move || syntax::detect_syntax(&syntax_set, path.as_deref(), &code))
         .await
+        .map_err(|err| err.to_string())
+}
+
+/// Returns how each theme file loaded, including the errors of files that failed to load.
+#[tauri::command]
+pub fn theme_load_report(state: State<'_, Mutex<AppState>>) -> ThemeLoadReport {
+    state
+        .lock()
+        .expect("Failed to lock state")
+        .theme_report
+        .clone()
+}
+
+/// Returns the syntax files that failed to load, their syntaxes are left out.
+#[tauri::command]
+pub fn syntax_load_report(state: State<'_, Mutex<AppState>>) -> Vec<FileError> {
+    state
+        .lock()
+        .expect("Failed to lock state")
+        .syntax_errors
+        .clone()
+}
+
+/// Opens a source file for snapshotting, optionally selecting a range of lines.
+#[tauri::command]
+pub async fn open_source_file(
+    state: State<'_, Mutex<AppState>>,
+    path: PathBuf,
+    range: Option<LineRange>,
+) -> Result<SourceFile, String> {
+    info!("Opening {}", path.display());
+
+    let syntax_set = state
+        .lock()
+        .expect("Failed to lock state")
+        .syntect_syntaxes
+        .clone();
+
+    tokio::task::spawn_blocking(move || snapshot::open_source_file(&syntax_set, &path, range))
+        .await
+        .map_err(|err| err.to_string())?
+        .map_err(|err| err.to_string())
+}
+
+/// Generates one output per file matching `pattern` with a saved preset.
+///
+/// The syntax of each file is detected, falling back to the preset's syntax. Progress is
+/// reported through `batch-progress` events, the batch runs as a generator job that can be
+/// cancelled with [cancel_generation], which skips the remaining files.
+///
+/// With `variants`, the light and dark variant of the preset's theme are generated, either as
+/// a single adaptive output or as two outputs, e.g. `main.rs.light.svg` and `main.rs.dark.svg`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn batch_generate(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    pattern: String,
+    preset_name: String,
+    output_dir: PathBuf,
+    variants: Option<bool>,
+) -> Result<Vec<BatchOutput>, String> {
+    info!("Batch generating {} with preset {}", pattern, preset_name);
+
+    let preset = PresetStore::open(presets_file(&app_handle))
+        .and_then(|store| store.get(&preset_name))
         .map_err(|err| err.to_string())?;
+    let inputs = snapshot::batch_inputs(&pattern).map_err(|err| err.to_string())?;
 
-        Ok(result?)
+    let variants = variants.unwrap_or_default();
+    let pairs = if variants {
+        load_pairs(&theme_pairs_file(&app_handle)).unwrap_or_else(|err| {
+            warn!("Failed to load theme pairs: {err}");
+            Vec::new()
+        })
     } else {
-        Err("Failed to find generator, syntax, or theme".to_string())
+        Vec::new()
+    };
+
+    let (syntax_set, theme, pair, generator, jobs) = {
+        let state = state.lock().expect("Failed to lock state");
+        (
+            state.syntect_syntaxes.clone(),
+            state.themes.theme(&preset.theme).cloned(),
+            variants
+                .then(|| state.themes.pair(&preset.theme, &pairs))
+                .flatten()
+                .map(|(light, dark)| (light.theme.clone(), dark.theme.clone())),
+            state
+                .generators
+                .iter()
+                .find(|(info, _)| info.name() == preset.generator)
+                .cloned(),
+            state.generator_jobs.clone(),
+        )
+    };
+
+    let theme = theme.ok_or(format!("Could not find theme \"{}\"", preset.theme))?;
+    let (info, generator) =
+        generator.ok_or(format!("Could not find generator \"{}\"", preset.generator))?;
+
+    if variants && pair.is_none() {
+        return Err(GenerateCodeError::NoVariants.to_string());
+    }
+
+    std::fs::create_dir_all(&output_dir).map_err(|err| err.to_string())?;
+
+    let base_dir = snapshot::pattern_base(&pattern);
+    let context = jobs.start();
+    let job_id = context.job_id;
+
+    let outputs = tokio::task::spawn_blocking(move || {
+        let total = inputs.len();
+
+        inputs
+            .into_iter()
+            .enumerate()
+            .take_while(|_| !context.is_cancelled())
+            .map(|(index, input)| {
+                let _ = app_handle.emit(
+                    "batch-progress",
+                    BatchProgress {
+                        job_id,
+                        current: index + 1,
+                        total,
+                        input: input.clone(),
+                    },
+                );
+
+                let result = (|| {
+                    let source = snapshot::open_source_file(&syntax_set, &input, None)
+                        .map_err(|err| err.to_string())?;
+                    let syntax = source
+                        .syntax
+                        .as_ref()
+                        .map(|detected| detected.name.as_str())
+                        .or(preset.syntax.as_deref())
+                        .and_then(|name| syntax_set.find_syntax_by_name(name))
+                        .unwrap_or_else(|| syntax_set.find_syntax_plain_text());
+                    let options = info
+                        .prepare_options(preset.options(), &source.code, &theme)
+                        .map_err(|errors| {
+                            errors
+                                .iter()
+                                .map(ToString::to_string)
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        })?;
+                    let output_path = snapshot::output_path(&info, &input, &base_dir, &output_dir);
+                    let outputs = match &pair {
+                        Some((light, dark)) => match generate_variants(
+                            generator.as_ref(),
+                            &source.code,
+                            light,
+                            dark,
+                            syntax,
+                            &syntax_set,
+                            &options,
+                            &context,
+                        )
+                        .map_err(|err| err.to_string())?
+                        {
+                            VariantOutput::Adaptive(output) => vec![(output_path, output)],
+                            VariantOutput::Separate { light, dark } => vec![
+                                (snapshot::variant_path(&output_path, "light"), light),
+                                (snapshot::variant_path(&output_path, "dark"), dark),
+                            ],
+                        },
+                        None => vec![(
+                            output_path,
+                            generator
+                                .generate_code(
+                                    &source.code,
+                                    &theme,
+                                    syntax,
+                                    &syntax_set,
+                                    &options,
+                                    &context,
+                                )
+                                .map_err(|err| err.to_string())?,
+                        )],
+                    };
+
+                    // NOTE: Generators stop with an empty output when cancelled, which must not
+                    // replace the file.
+                    if context.is_cancelled() {
+                        return Ok(None);
+                    }
+
+                    for (path, output) in &outputs {
+                        if let Some(parent) = path.parent() {
+                            std::fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+                        }
+                        std::fs::write(path, output).map_err(|err| err.to_string())?;
+                    }
+
+                    Ok::<_, String>(Some(outputs.into_iter().map(|(path, _)| path).collect()))
+                })();
+
+                match result {
+                    Ok(Some(outputs)) => BatchOutput {
+                        input,
+                        outputs,
+                        cancelled: false,
+                        error: None,
+                    },
+                    Ok(None) => BatchOutput {
+                        input,
+                        outputs: Vec::new(),
+                        cancelled: true,
+                        error: None,
+                    },
+                    Err(error) => {
+                        warn!("Failed to generate {}: {error}", input.display());
+                        BatchOutput {
+                            input,
+                            outputs: Vec::new(),
+                            cancelled: false,
+                            error: Some(error),
+                        }
+                    }
+                }
+            })
+            .collect()
+    })
+    .await;
+
+    jobs.finish(job_id);
+    outputs.map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+pub fn theme_pairs(app_handle: AppHandle) -> Result<Vec<ThemePair>, String> {
+    load_pairs(&theme_pairs_file(&app_handle)).map_err(|err| err.to_string())
+}
+
+/// Replaces the stored theme pairs after checking that every pair has a light and a dark theme.
+#[tauri::command]
+pub fn save_theme_pairs(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    pairs: Vec<ThemePair>,
+) -> Result<(), String> {
+    {
+        let state = state.lock().expect("Failed to lock state");
+
+        for pair in &pairs {
+            state
+                .themes
+                .validate_pair(pair)
+                .map_err(|err| err.to_string())?;
+        }
     }
+
+    save_pairs(&theme_pairs_file(&app_handle), &pairs).map_err(|err| err.to_string())
+}
+
+/// Returns the variant of a theme with the opposite appearance, if there is one.
+#[tauri::command]
+pub fn theme_counterpart(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    theme: String,
+) -> Result<Option<ThemeInfo>, String> {
+    let pairs = load_pairs(&theme_pairs_file(&app_handle)).map_err(|err| err.to_string())?;
+    let state = state.lock().expect("Failed to lock state");
+
+    Ok(state
+        .themes
+        .counterpart(&theme, &pairs)
+        .map(|registered| registered.info.clone()))
+}
+
+/// Installs the theme files of a color scheme from the asset store into the store theme
+/// directory, where the theme watcher loads them as store themes.
+#[tauri::command]
+pub async fn install_theme_asset(
+    app_handle: AppHandle,
+    asset: AssetData,
+) -> Result<Vec<PathBuf>, String> {
+    info!("Installing theme asset {}", asset.name);
+
+    let api = GithubApi::new(reqwest::Client::new(), None);
+    // NOTE: Keep the receiver alive so progress messages don't fail to send.
+    let (progress, _messages) = async_channel::unbounded();
+
+    asset_store::install_theme_files(&api, &asset, &store_theme_dir(&app_handle), &progress)
+        .await
+        .map_err(|err| err.to_string())
 }
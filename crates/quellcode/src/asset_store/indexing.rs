@@ -1,6 +1,6 @@
 use async_channel::Sender;
 use color_eyre::eyre::Result;
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 use tokio::sync::Semaphore;
 use url::Url;
 
@@ -10,6 +10,7 @@ use crate::{
         package_control::{get_package, get_package_from_url},
     },
     util::send_async_channel,
+    ThemeFormat,
 };
 
 use super::{AssetData, AssetType, FileInfo, ProgressMessage, ProgressMessageKind};
@@ -236,8 +237,8 @@ pub async fn get_theme_files(
     }) {
         find_files_from_github_url(api, url, progress, |item| {
             matches!(
-                item.path.rsplit('.').next(),
-                Some(ext) if ext == "tmTheme" || ext == "sublime-color-scheme"
+                ThemeFormat::from_path(Path::new(&item.path)),
+                Some(ThemeFormat::Sublime | ThemeFormat::TmTheme)
             )
         })
         .await
@@ -0,0 +1,59 @@
+use async_channel::Sender;
+use color_eyre::eyre::{eyre, Result};
+use std::path::{Path, PathBuf};
+
+use crate::{
+    scraping::{github_api::GithubApi, package_control::get_package_from_url},
+    util::send_async_channel,
+};
+
+use super::{AssetData, AssetType};
+
+/// Downloads the theme files of a color scheme asset into `store_dir`, which should be
+/// [crate::dir::store_theme_dir] so they are loaded as store themes.
+///
+/// Returns the paths of the written files, files of a previous install are replaced.
+pub async fn install_theme_files(
+    api: &GithubApi,
+    asset: &AssetData,
+    store_dir: &Path,
+    progress: &Sender<String>,
+) -> Result<Vec<PathBuf>> {
+    if asset.kind != AssetType::ColorScheme {
+        return Err(eyre!("{} is not a color scheme", asset.name));
+    }
+
+    send_async_channel(progress, "Getting package".to_string()).await;
+    let package = get_package_from_url(&asset.url).await?;
+
+    let Some(url) = package.sources.iter().find(|source| {
+        source
+            .host_str()
+            .is_some_and(|host| host.contains("github.com"))
+    }) else {
+        return Err(eyre!("{} has no GitHub source", asset.name));
+    };
+
+    send_async_channel(progress, "Getting repo info".to_string()).await;
+    let repo_info = api.get_repo_info_from_url(url).await?;
+
+    std::fs::create_dir_all(store_dir)?;
+
+    let mut paths = Vec::new();
+    for file in &asset.files {
+        let file_name = format!("{}.{}", file.name, file.extension);
+        send_async_channel(progress, format!("Downloading {file_name}")).await;
+
+        let text = api
+            .get_file_text(&repo_info.owner.login, &repo_info.name, &file.sha256)
+            .await?;
+        let path = store_dir.join(file_name);
+
+        std::fs::write(&path, text)?;
+        paths.push(path);
+    }
+
+    send_async_channel(progress, format!("Installed {} files", paths.len())).await;
+
+    Ok(paths)
+}
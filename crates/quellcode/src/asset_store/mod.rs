@@ -1,7 +1,9 @@
 mod asset;
 mod indexing;
+mod install;
 mod message;
 
 pub use asset::*;
 pub use indexing::*;
+pub use install::*;
 pub use message::*;
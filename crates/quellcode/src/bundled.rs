@@ -0,0 +1,211 @@
+//! Syntaxes and themes compiled by `build.rs` and embedded in the app.
+//!
+//! The syntaxes are bat's syntaxes packaged by two-face plus the syntaxes in `data/syntaxes`,
+//! the themes are the ones in `data/themes`.
+
+use syntect::{highlighting::ThemeSet, parsing::SyntaxSet};
+
+const SYNTAXES: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/syntaxes.packdump"));
+/// Hash of the embedded syntaxes, so caches of syntaxes built on top of them can be invalidated.
+pub(crate) const SYNTAXES_HASH: &str = env!("QUELLCODE_SYNTAXES_HASH");
+const THEMES: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/themes.themedump"));
+/// Licenses of the bundled syntaxes as Markdown.
+pub const SYNTAX_LICENSES: &str = include_str!(concat!(env!("OUT_DIR"), "/syntax-licenses.md"));
+
+/// The bundled syntaxes, a superset of syntect's default syntaxes.
+pub fn syntaxes() -> SyntaxSet {
+    syntect::dumps::from_binary(SYNTAXES)
+}
+
+/// The bundled themes, without syntect's default themes.
+pub fn themes() -> ThemeSet {
+    syntect::dumps::from_binary(THEMES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_bundled_assets() {
+        let syntax_set = syntaxes();
+        assert!(syntax_set.find_syntax_by_name("Rust").is_some());
+        assert!(syntax_set.find_syntax_by_extension("toml").is_some());
+
+        assert!(themes().themes.contains_key("Nord"));
+    }
+
+    #[test]
+    fn load_extra_syntaxes() {
+        use syntect::parsing::ParseState;
+
+        let samples = [
+            (
+                "TOML",
+                "toml",
+                r#"[package]
+name = "quellcode" # name
+version = 1"#,
+            ),
+            (
+                "TypeScript",
+                "ts",
+                r#"const answer: number = 42;
+function id<T>(value: T): T {
+    return `${value}`;
+}"#,
+            ),
+            (
+                "TypeScriptReact",
+                "tsx",
+                r#"const App = () => <div className="app">{count > 1 && <b>Hi</b>}</div>;"#,
+            ),
+            (
+                "Svelte",
+                "svelte",
+                r#"<script lang="ts">
+    let count = 0;
+</script>
+{#if count}<button on:click={() => count++}>{count}</button>{/if}"#,
+            ),
+            (
+                "Vue Component",
+                "vue",
+                r#"<template>
+    <p v-if="ok" @click="go(1)">{{ message }}</p>
+</template>
+<style scoped>p { color: red; }</style>"#,
+            ),
+            (
+                "Dockerfile",
+                "Dockerfile",
+                r#"FROM rust:1 AS build
+RUN cargo build --release \
+    && strip target/app
+ENV PATH="${HOME}/bin""#,
+            ),
+            (
+                "Zig",
+                "zig",
+                r#"const std = @import("std");
+pub fn main() !void {
+    std.debug.print("{d}\n", .{42});
+}"#,
+            ),
+            (
+                "Kotlin",
+                "kt",
+                r#"data class Point(val x: Int)
+fun main() {
+    println("x = ${Point(1).x}") /* nested /* comment */ */
+}"#,
+            ),
+            (
+                "Swift",
+                "swift",
+                r#"@MainActor
+struct Point { let x: Int }
+func show(_ p: Point) -> String { "x = \(p.x)" }"#,
+            ),
+            (
+                "Dart",
+                "dart",
+                r#"void main() {
+    final name = 'Dart';
+    print('Hello ${name.length} $name');
+}"#,
+            ),
+            (
+                "Terraform",
+                "tf",
+                r#"resource "aws_instance" "web" {
+    ami  = var.ami
+    tags = { Name = "web-${count.index}" }
+    user_data = <<-EOF
+        echo hi
+    EOF
+}"#,
+            ),
+            (
+                "Nix",
+                "nix",
+                r#"{ pkgs ? import <nixpkgs> {} }:
+let name = "hello"; in pkgs.mkShell {
+    shellHook = ''
+        echo ${name}
+    '';
+}"#,
+            ),
+            (
+                "Elixir",
+                "ex",
+                r#"defmodule Greeter do
+    @moduledoc "Greets"
+    def hello(name), do: "Hello #{name}" |> String.trim()
+end"#,
+            ),
+            (
+                "GraphQL",
+                "graphql",
+                r#"query Hero($id: ID!) @cached {
+    hero(id: $id) { name ...Friends }
+}
+type Hero implements Node { id: ID! }"#,
+            ),
+            (
+                "Protocol Buffer",
+                "proto",
+                r#"syntax = "proto3";
+message Point {
+    int32 x = 1;
+    map<string, Point> children = 2;
+}"#,
+            ),
+            (
+                "Fish",
+                "fish",
+                r#"function greet --argument name
+    echo "Hello $name" (date) # greet
+end"#,
+            ),
+            (
+                "PowerShell",
+                "ps1",
+                r#"function Get-Answer {
+    param([int]$Value = 42)
+    Write-Output "Answer: $($Value * 1)" -NoNewline
+}"#,
+            ),
+        ];
+
+        let syntax_set = syntaxes();
+
+        for (name, extension, sample) in samples {
+            let syntax = syntax_set
+                .find_syntax_by_extension(extension)
+                .unwrap_or_else(|| panic!("{name} is not bundled"));
+            assert_eq!(syntax.name, name);
+
+            let mut state = ParseState::new(syntax);
+            let operations: usize = sample
+                .lines()
+                .map(|line| state.parse_line(line, &syntax_set).unwrap().len())
+                .sum();
+            assert!(operations > 0, "{name} highlights nothing");
+        }
+
+        let pattern = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/syntaxes/**/*.sublime-syntax"
+        );
+        for path in glob::glob(pattern).unwrap().map(Result::unwrap) {
+            assert!(
+                path.parent().unwrap().join("LICENSE").is_file(),
+                "{} has no license",
+                path.display()
+            );
+        }
+
+        assert!(SYNTAX_LICENSES.contains("TypeScript/LICENSE"));
+    }
+}
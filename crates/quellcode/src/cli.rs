@@ -0,0 +1,96 @@
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use log::{info, warn};
+use serde::Serialize;
+use syntect::parsing::SyntaxSet;
+use ts_rs::TS;
+
+use crate::{syntax::detect_syntax, theme::load_theme, ThemeFormat};
+
+/// Command line arguments, applied by the frontend once it is ready.
+#[derive(Debug, Clone, Default, Parser, Serialize, TS)]
+#[command(version, about)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Cli {
+    /// Source file to open
+    #[ts(optional)]
+    pub file: Option<PathBuf>,
+    /// Syntax of the file, detected from its name and content if omitted
+    #[arg(long)]
+    #[ts(optional)]
+    pub syntax: Option<String>,
+    /// Name of a saved preset to load at startup
+    #[arg(long)]
+    #[ts(optional)]
+    pub preset: Option<String>,
+    /// Check that a theme file loads and print its errors and warnings, without starting the app
+    #[arg(long, value_name = "FILE")]
+    #[serde(skip)]
+    #[ts(skip)]
+    pub check_theme: Option<PathBuf>,
+}
+
+impl Cli {
+    /// Detects the syntax of [Cli::file] if `--syntax` is omitted or doesn't name a syntax.
+    pub fn resolve_syntax(&mut self, syntax_set: &SyntaxSet) {
+        if let Some(syntax) = &self.syntax {
+            if syntax_set.find_syntax_by_name(syntax).is_some() {
+                return;
+            }
+
+            warn!("Syntax \"{syntax}\" not found, detecting it instead");
+            self.syntax = None;
+        }
+
+        let Some(file) = &self.file else {
+            return;
+        };
+
+        match std::fs::read_to_string(file) {
+            Ok(text) => {
+                if let Some(detected) = detect_syntax(syntax_set, Some(file), &text) {
+                    info!(
+                        "Detected syntax {} for {} from {:?}",
+                        detected.name,
+                        file.display(),
+                        detected.method
+                    );
+                    self.syntax = Some(detected.name);
+                }
+            }
+            Err(err) => warn!("Failed to read {}: {err}", file.display()),
+        }
+    }
+}
+
+/// Loads the theme file at `path` and prints whether it loaded, returning the exit code.
+pub fn check_theme(path: &Path) -> i32 {
+    let Some(format) = ThemeFormat::from_path(path) else {
+        eprintln!("{} is not a supported theme file", path.display());
+        return 2;
+    };
+
+    match load_theme(path, &format) {
+        Ok(loaded) => {
+            println!("Loaded theme \"{}\" from {}", loaded.name, path.display());
+
+            for warning in &loaded.warnings {
+                println!("  warning: {warning}");
+            }
+
+            0
+        }
+        Err(err) => {
+            eprint!("{}", err.render(path));
+            1
+        }
+    }
+}
+
+/// Returns the arguments the app was started with.
+#[tauri::command]
+pub fn cli_args(cli: tauri::State<'_, Cli>) -> Cli {
+    cli.inner().clone()
+}
@@ -32,6 +32,11 @@ pub fn code_theme_dir(app_handle: &tauri::AppHandle) -> PathBuf {
     data_dir(app_handle).join("themes")
 }
 
+/// Themes installed from the asset store, kept apart from the themes added by the user.
+pub fn store_theme_dir(app_handle: &tauri::AppHandle) -> PathBuf {
+    code_theme_dir(app_handle).join("store")
+}
+
 pub fn code_syntax_dir(app_handle: &tauri::AppHandle) -> PathBuf {
     data_dir(app_handle).join("syntaxes")
 }
@@ -39,3 +44,15 @@ pub fn code_syntax_dir(app_handle: &tauri::AppHandle) -> PathBuf {
 pub fn store_cache_dir(app_handle: &tauri::AppHandle) -> PathBuf {
     cache_dir(app_handle).join("asset_store")
 }
+
+pub fn presets_file(app_handle: &tauri::AppHandle) -> PathBuf {
+    config_dir(app_handle).join(crate::preset::PRESETS_FILE)
+}
+
+pub fn config_file(app_handle: &tauri::AppHandle) -> PathBuf {
+    config_dir(app_handle).join(crate::settings::CONFIG_FILE)
+}
+
+pub fn theme_pairs_file(app_handle: &tauri::AppHandle) -> PathBuf {
+    config_dir(app_handle).join(crate::theme::THEME_PAIRS_FILE)
+}
@@ -0,0 +1,309 @@
+//! Expression valued generator properties.
+//!
+//! Properties can be given as an expression instead of a plain value, e.g.
+//! `lines * font_size * 1.4 + 64`, which is evaluated with [evalexpr] against the
+//! [SnippetMetrics] of the code being generated before the properties are validated.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use evalexpr::{
+    eval_with_context, ContextWithMutableVariables, DefaultNumericTypes, EvalexprError,
+    HashMapContext, Value,
+};
+use syntect::highlighting::Theme;
+
+use crate::property::{PropertyColor, PropertyError, PropertyInfo, PropertyValue};
+
+type Context = HashMapContext<DefaultNumericTypes>;
+
+/// Measurements of a code snippet that expressions can refer to.
+#[derive(Debug, Clone, Default)]
+pub struct SnippetMetrics {
+    /// Number of lines, available as `lines`
+    pub lines: usize,
+    /// Number of characters in the longest line, available as `longest_line`
+    pub longest_line: usize,
+    /// Number of characters in the snippet, available as `characters`
+    pub characters: usize,
+    /// Font size in pixels, available as `font_size`
+    pub font_size: f32,
+    /// Theme background as a hex string, available as `background`
+    pub background: Option<PropertyColor>,
+    /// Theme foreground as a hex string, available as `foreground`
+    pub foreground: Option<PropertyColor>,
+}
+
+impl SnippetMetrics {
+    pub fn new(text: &str, theme: &Theme, font_size: f32) -> SnippetMetrics {
+        SnippetMetrics {
+            lines: text.lines().count(),
+            longest_line: text
+                .lines()
+                .map(|line| line.chars().count())
+                .max()
+                .unwrap_or(0),
+            characters: text.chars().count(),
+            font_size,
+            background: theme.settings.background.map(PropertyColor::from),
+            foreground: theme.settings.foreground.map(PropertyColor::from),
+        }
+    }
+
+    fn variables(&self) -> BTreeMap<String, Value> {
+        let mut variables = BTreeMap::from([
+            ("lines".to_string(), Value::Int(self.lines as i64)),
+            (
+                "longest_line".to_string(),
+                Value::Int(self.longest_line as i64),
+            ),
+            ("characters".to_string(), Value::Int(self.characters as i64)),
+            ("font_size".to_string(), Value::Float(self.font_size as f64)),
+        ]);
+
+        for (name, color) in [
+            ("background", self.background),
+            ("foreground", self.foreground),
+        ] {
+            if let Some(color) = color {
+                variables.insert(name.to_string(), Value::String(color.to_hex(true)));
+            }
+        }
+
+        variables
+    }
+}
+
+/// Evaluates a single numeric expression, e.g. the font size, against `metrics`.
+pub fn evaluate_number(
+    name: &str,
+    expression: &str,
+    metrics: &SnippetMetrics,
+) -> Result<f64, PropertyError> {
+    let context = build_context(&metrics.variables());
+
+    eval_with_context(expression, &context)
+        .map_err(|err| invalid_expression(name, expression, err))?
+        .as_number()
+        .map_err(|err| invalid_expression(name, expression, err))
+}
+
+/// Evaluates every [PropertyValue::Expression] in `values`.
+///
+/// Expressions can refer to the snippet metrics, to the values or defaults of other
+/// properties and to other expressions as long as they don't depend on each other.
+/// Results for integer properties are rounded.
+pub fn evaluate_properties(
+    properties: &[PropertyInfo],
+    values: BTreeMap<String, PropertyValue>,
+    metrics: &SnippetMetrics,
+) -> Result<BTreeMap<String, PropertyValue>, Vec<PropertyError>> {
+    let mut variables = metrics.variables();
+    let mut pending = BTreeMap::new();
+    let mut evaluated = BTreeMap::new();
+
+    for property in properties {
+        if values.contains_key(property.name()) {
+            continue;
+        }
+
+        if let Some(value) = property
+            .default_value()
+            .as_ref()
+            .and_then(to_expression_value)
+        {
+            variables.insert(property.name().to_string(), value);
+        }
+    }
+
+    for (name, value) in values {
+        match value {
+            PropertyValue::Expression { expression } => {
+                pending.insert(name, expression);
+            }
+            value => {
+                if let Some(variable) = to_expression_value(&value) {
+                    variables.insert(name.clone(), variable);
+                }
+
+                evaluated.insert(name, value);
+            }
+        }
+    }
+
+    let mut errors = Vec::new();
+
+    while !pending.is_empty() {
+        let context = build_context(&variables);
+        let waiting: BTreeSet<String> = pending.keys().cloned().collect();
+        let mut deferred = BTreeMap::new();
+
+        for (name, expression) in std::mem::take(&mut pending) {
+            let property = properties.iter().find(|property| property.name() == name);
+
+            match eval_with_context(&expression, &context) {
+                Ok(value) => match to_property_value(property, value) {
+                    Ok(value) => {
+                        if let Some(variable) = to_expression_value(&value) {
+                            variables.insert(name.clone(), variable);
+                        }
+
+                        evaluated.insert(name, value);
+                    }
+                    Err(err) => errors.push(PropertyError::InvalidProperty {
+                        property: name,
+                        source: Box::new(err),
+                    }),
+                },
+                Err(EvalexprError::VariableIdentifierNotFound(identifier))
+                    if waiting.contains(&identifier) =>
+                {
+                    deferred.insert(name, expression);
+                }
+                Err(err) => errors.push(invalid_expression(&name, &expression, err)),
+            }
+        }
+
+        if deferred.len() == waiting.len() {
+            for (name, expression) in deferred {
+                errors.push(invalid_expression(
+                    &name,
+                    &expression,
+                    "expression depends on itself through other properties",
+                ));
+            }
+
+            break;
+        }
+
+        pending = deferred;
+    }
+
+    if errors.is_empty() {
+        Ok(evaluated)
+    } else {
+        Err(errors)
+    }
+}
+
+fn build_context(variables: &BTreeMap<String, Value>) -> Context {
+    let mut context = Context::new();
+
+    for (name, value) in variables {
+        // NOTE: Each identifier is only set once, so this can't fail on a type mismatch.
+        let _ = context.set_value(name.clone(), value.clone());
+    }
+
+    context
+}
+
+fn invalid_expression(name: &str, expression: &str, err: impl ToString) -> PropertyError {
+    PropertyError::InvalidProperty {
+        property: name.to_string(),
+        source: Box::new(PropertyError::InvalidExpression {
+            expression: expression.to_string(),
+            message: err.to_string(),
+        }),
+    }
+}
+
+fn to_expression_value(value: &PropertyValue) -> Option<Value> {
+    match value {
+        PropertyValue::String(string) => Some(Value::String(string.clone())),
+        PropertyValue::Int(int) => Some(Value::Int(*int as i64)),
+        PropertyValue::Float(float) => Some(Value::Float(*float)),
+        PropertyValue::Bool(bool) => Some(Value::Boolean(*bool)),
+        PropertyValue::Color(color) => Some(Value::String(color.to_hex(true))),
+        PropertyValue::LineRanges(_) | PropertyValue::Expression { .. } => None,
+    }
+}
+
+fn to_property_value(
+    property: Option<&PropertyInfo>,
+    value: Value,
+) -> Result<PropertyValue, PropertyError> {
+    match (property, value) {
+        (Some(PropertyInfo::Integer { .. }), Value::Float(float)) => {
+            Ok(PropertyValue::Int(float.round() as i32))
+        }
+        (_, Value::Int(int)) => i32::try_from(int)
+            .map(PropertyValue::Int)
+            .map_err(|_| PropertyError::InvalidValueType),
+        (_, Value::Float(float)) => Ok(PropertyValue::Float(float)),
+        (_, Value::Boolean(bool)) => Ok(PropertyValue::Bool(bool)),
+        (_, Value::String(string)) => Ok(PropertyValue::String(string)),
+        _ => Err(PropertyError::InvalidValueType),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn integer(name: &str, default: Option<i32>) -> PropertyInfo {
+        PropertyInfo::Integer {
+            name: name.to_string(),
+            description: String::new(),
+            default,
+            min: None,
+            max: None,
+            step: None,
+            depends_on: Vec::new(),
+            display_name: None,
+            disables: Vec::new(),
+        }
+    }
+
+    fn expression(expression: &str) -> PropertyValue {
+        PropertyValue::Expression {
+            expression: expression.to_string(),
+        }
+    }
+
+    #[test]
+    fn evaluate_metrics_expressions() {
+        let metrics = SnippetMetrics {
+            lines: 10,
+            longest_line: 40,
+            font_size: 16.0,
+            ..Default::default()
+        };
+
+        let properties = [integer("width", None), integer("height", None)];
+        let values = evaluate_properties(
+            &properties,
+            BTreeMap::from([
+                (
+                    "height".to_string(),
+                    expression("lines * font_size * 1.4 + 64"),
+                ),
+                ("width".to_string(), expression("height * 2")),
+            ]),
+            &metrics,
+        )
+        .unwrap();
+
+        assert_eq!(values.get("height"), Some(&PropertyValue::Int(288)));
+        assert_eq!(values.get("width"), Some(&PropertyValue::Int(576)));
+
+        assert_eq!(
+            evaluate_number("font_size", "max(12, 600 / longest_line)", &metrics).unwrap(),
+            15.0
+        );
+    }
+
+    #[test]
+    fn report_circular_expressions() {
+        let properties = [integer("width", Some(10)), integer("height", Some(10))];
+        let errors = evaluate_properties(
+            &properties,
+            BTreeMap::from([
+                ("height".to_string(), expression("width")),
+                ("width".to_string(), expression("height")),
+            ]),
+            &SnippetMetrics::default(),
+        )
+        .unwrap_err();
+
+        assert_eq!(errors.len(), 2);
+    }
+}
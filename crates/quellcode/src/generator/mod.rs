@@ -1,9 +1,13 @@
 use color_eyre::eyre::Result;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap},
     fmt::Debug,
-    sync::{atomic::AtomicBool, mpsc::Sender, Arc},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        mpsc::Sender,
+        Arc, Mutex,
+    },
 };
 
 use syntect::{
@@ -12,7 +16,10 @@ use syntect::{
 };
 use ts_rs::TS;
 
-use super::property::*;
+use super::{
+    expression::{evaluate_number, evaluate_properties, SnippetMetrics},
+    property::*,
+};
 
 pub mod svg;
 pub use svg::SvgGenerator;
@@ -23,6 +30,9 @@ pub use resolve::FusionGenerator;
 type Properties = Vec<PropertyInfo>;
 type Extensions = Vec<&'static str>;
 
+/// Identifier of a single generation request.
+pub type JobId = u32;
+
 #[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase", tag = "kind")]
 #[ts(export)]
@@ -30,6 +40,31 @@ pub enum GeneratorEvent {
     Started,
     Cancelled,
     Progress { message: Option<String>, step: u8 },
+    Finished { output: String },
+    FinishedVariants { light: String, dark: String },
+    Failed { error: String },
+}
+
+/// Output of generating the light and dark variant of a theme in one run.
+#[derive(Debug, Clone)]
+pub enum VariantOutput {
+    /// A single output that follows the color scheme preferred by the viewer
+    Adaptive(String),
+    Separate {
+        light: String,
+        dark: String,
+    },
+}
+
+impl From<VariantOutput> for GeneratorEvent {
+    fn from(output: VariantOutput) -> Self {
+        match output {
+            VariantOutput::Adaptive(output) => GeneratorEvent::Finished { output },
+            VariantOutput::Separate { light, dark } => {
+                GeneratorEvent::FinishedVariants { light, dark }
+            }
+        }
+    }
 }
 
 impl GeneratorEvent {
@@ -41,19 +76,108 @@ impl GeneratorEvent {
     }
 }
 
+/// A [GeneratorEvent] tagged with the job it belongs to.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct GeneratorJobEvent {
+    pub job_id: JobId,
+    #[serde(flatten)]
+    pub event: GeneratorEvent,
+}
+
+/// Context handed to a generator for a single job.
 #[derive(Debug, Clone)]
 pub struct GeneratorContext {
-    pub event_tx: Sender<GeneratorEvent>,
+    pub job_id: JobId,
+    pub event_tx: Sender<GeneratorJobEvent>,
     pub cancel: Arc<AtomicBool>,
 }
 
 impl GeneratorContext {
-    pub fn new(event_tx: Sender<GeneratorEvent>) -> GeneratorContext {
+    pub fn new(job_id: JobId, event_tx: Sender<GeneratorJobEvent>) -> GeneratorContext {
         GeneratorContext {
-            event_tx: event_tx.clone(),
+            job_id,
+            event_tx,
             cancel: Arc::new(AtomicBool::new(false)),
         }
     }
+
+    /// Sends an event tagged with this context's job id.
+    pub fn send(&self, event: GeneratorEvent) -> Result<()> {
+        self.event_tx.send(GeneratorJobEvent {
+            job_id: self.job_id,
+            event,
+        })?;
+
+        Ok(())
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+}
+
+/// Keeps track of running generator jobs so they can be cancelled independently.
+#[derive(Debug, Clone)]
+pub struct GeneratorJobs {
+    event_tx: Sender<GeneratorJobEvent>,
+    next_id: Arc<AtomicU32>,
+    running: Arc<Mutex<HashMap<JobId, Arc<AtomicBool>>>>,
+}
+
+impl GeneratorJobs {
+    pub fn new(event_tx: Sender<GeneratorJobEvent>) -> GeneratorJobs {
+        GeneratorJobs {
+            event_tx,
+            next_id: Arc::new(AtomicU32::new(1)),
+            running: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Registers a new job and returns the context the generator should run with.
+    pub fn start(&self) -> GeneratorContext {
+        let job_id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let context = GeneratorContext::new(job_id, self.event_tx.clone());
+
+        self.running
+            .lock()
+            .expect("Failed to lock generator jobs")
+            .insert(job_id, context.cancel.clone());
+
+        context
+    }
+
+    /// Requests cancellation of a job, returns `false` if the job isn't running.
+    pub fn cancel(&self, job_id: JobId) -> bool {
+        match self
+            .running
+            .lock()
+            .expect("Failed to lock generator jobs")
+            .get(&job_id)
+        {
+            Some(cancel) => {
+                cancel.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes a job from the running list once it has completed.
+    pub fn finish(&self, job_id: JobId) {
+        self.running
+            .lock()
+            .expect("Failed to lock generator jobs")
+            .remove(&job_id);
+    }
+
+    pub fn is_running(&self, job_id: JobId) -> bool {
+        self.running
+            .lock()
+            .expect("Failed to lock generator jobs")
+            .contains_key(&job_id)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, TS)]
@@ -102,6 +226,59 @@ impl GeneratorInfo {
     pub fn saveable(&self) -> bool {
         self.saveable
     }
+    pub fn properties(&self) -> &[PropertyInfo] {
+        self.properties.as_deref().unwrap_or_default()
+    }
+
+    /// Keeps the values of the properties this generator declares, so options chosen for
+    /// another generator can be carried over without failing validation.
+    pub fn declared_options(
+        &self,
+        mut values: BTreeMap<String, PropertyValue>,
+    ) -> BTreeMap<String, PropertyValue> {
+        values.retain(|name, _| {
+            self.properties()
+                .iter()
+                .any(|property| property.name() == name)
+        });
+        values
+    }
+
+    /// Validates the extra properties in `options` and fills in missing defaults.
+    pub fn validate_options(
+        &self,
+        options: GeneratorOptions,
+    ) -> Result<GeneratorOptions, Vec<PropertyError>> {
+        let extra = validate_properties(self.properties(), options.extra)?;
+
+        Ok(GeneratorOptions { extra, ..options })
+    }
+
+    /// Evaluates expressions in `options` against the snippet, then validates them.
+    ///
+    /// The font size expression is evaluated first so property expressions see the final size.
+    pub fn prepare_options(
+        &self,
+        options: GeneratorOptions,
+        text: &str,
+        theme: &Theme,
+    ) -> Result<GeneratorOptions, Vec<PropertyError>> {
+        let mut metrics = SnippetMetrics::new(text, theme, options.font_size);
+
+        if let Some(expression) = &options.font_size_expression {
+            metrics.font_size =
+                evaluate_number("font_size", expression, &metrics).map_err(|err| vec![err])? as f32;
+        }
+
+        let extra = evaluate_properties(self.properties(), options.extra, &metrics)?;
+
+        self.validate_options(GeneratorOptions {
+            font_size: metrics.font_size,
+            font_size_expression: None,
+            extra,
+            ..options
+        })
+    }
 }
 
 #[derive(Debug, Default, Clone, Deserialize, TS)]
@@ -109,6 +286,10 @@ impl GeneratorInfo {
 #[ts(export)]
 pub struct GeneratorOptions {
     pub font_size: f32,
+    /// Expression that overrides `font_size`, e.g. `max(12, 600 / longest_line)`
+    #[serde(default)]
+    #[ts(optional)]
+    pub font_size_expression: Option<String>,
     pub font_family: String,
     pub extra: BTreeMap<String, PropertyValue>,
 }
@@ -123,8 +304,101 @@ pub trait Generator: Send + Sync + Debug {
         options: &GeneratorOptions,
         context: &GeneratorContext,
     ) -> Result<String>;
+
+    /// Generates a single output that switches between the `light` and `dark` theme depending
+    /// on the color scheme preferred by the viewer.
+    ///
+    /// Returns `None` if the output format can't switch themes.
+    #[allow(clippy::too_many_arguments)]
+    fn generate_adaptive(
+        &self,
+        _text: &str,
+        _light: &Theme,
+        _dark: &Theme,
+        _syntax: &SyntaxReference,
+        _syntax_set: &SyntaxSet,
+        _options: &GeneratorOptions,
+        _context: &GeneratorContext,
+    ) -> Option<Result<String>> {
+        None
+    }
+}
+
+/// Generates the light and dark variant of a theme, as a single adaptive output if the generator
+/// supports it and as two outputs otherwise.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_variants(
+    generator: &dyn Generator,
+    text: &str,
+    light: &Theme,
+    dark: &Theme,
+    syntax: &SyntaxReference,
+    syntax_set: &SyntaxSet,
+    options: &GeneratorOptions,
+    context: &GeneratorContext,
+) -> Result<VariantOutput> {
+    if let Some(output) =
+        generator.generate_adaptive(text, light, dark, syntax, syntax_set, options, context)
+    {
+        return output.map(VariantOutput::Adaptive);
+    }
+
+    Ok(VariantOutput::Separate {
+        light: generator.generate_code(text, light, syntax, syntax_set, options, context)?,
+        dark: generator.generate_code(text, dark, syntax, syntax_set, options, context)?,
+    })
 }
 
 pub trait GeneratorExt {
     fn information() -> GeneratorInfo;
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+
+    use super::*;
+
+    #[test]
+    fn cancel_jobs_independently() {
+        let (tx, rx) = channel();
+        let jobs = GeneratorJobs::new(tx);
+
+        let first = jobs.start();
+        let second = jobs.start();
+
+        assert_ne!(first.job_id, second.job_id);
+        assert!(jobs.cancel(first.job_id));
+        assert!(first.is_cancelled());
+        assert!(!second.is_cancelled());
+
+        second.send(GeneratorEvent::Started).unwrap();
+        assert_eq!(rx.recv().unwrap().job_id, second.job_id);
+
+        jobs.finish(first.job_id);
+        assert!(!jobs.is_running(first.job_id));
+        assert!(!jobs.cancel(first.job_id));
+    }
+
+    #[test]
+    fn switch_generators_with_stale_options() {
+        let svg = SvgGenerator::information();
+        let fusion = FusionGenerator::information();
+        let options = GeneratorOptions {
+            extra: BTreeMap::from([("include_background".to_string(), false.into())]),
+            ..Default::default()
+        };
+
+        let svg_options = svg.validate_options(options).unwrap();
+        assert!(fusion.validate_options(svg_options.clone()).is_err());
+
+        let extra = fusion.declared_options(svg_options.extra.clone());
+        assert!(!extra.contains_key("include_background"));
+        assert!(fusion
+            .validate_options(GeneratorOptions {
+                extra,
+                ..svg_options
+            })
+            .is_ok());
+    }
+}
@@ -1,4 +1,4 @@
-use std::{fmt::Display, sync::atomic::Ordering};
+use std::fmt::Display;
 
 use syntect::easy::HighlightLines;
 
@@ -79,7 +79,7 @@ impl Generator for FusionGenerator {
         options: &GeneratorOptions,
         context: &GeneratorContext,
     ) -> Result<String> {
-        let _ = context.event_tx.send(GeneratorEvent::Started);
+        let _ = context.send(GeneratorEvent::Started);
         let mut result = String::new();
 
         let text = if options
@@ -95,7 +95,7 @@ impl Generator for FusionGenerator {
                 &" ".repeat(
                     options
                         .extra
-                        .get("tab_size")
+                        .get("spaces_per_tab")
                         .and_then(|value| value.clone().try_into().ok())
                         .unwrap_or(4) as usize,
                 ),
@@ -158,10 +158,10 @@ impl Generator for FusionGenerator {
         let mut document_offset = 0;
         let mut highlight = HighlightLines::new(syntax, theme);
 
-        let _ = context.event_tx.send(GeneratorEvent::progress(1, Some("Generating color ranges")));
+        let _ = context.send(GeneratorEvent::progress(1, Some("Generating color ranges")));
         for line in text.lines() {
-            if context.cancel.load(Ordering::Relaxed) {
-                let _ = context.event_tx.send(GeneratorEvent::Cancelled);
+            if context.is_cancelled() {
+                let _ = context.send(GeneratorEvent::Cancelled);
                 return Ok(String::new());
             }
 
@@ -216,32 +216,32 @@ impl GeneratorExt for FusionGenerator {
                 PropertyInfo::Integer {
                     name: "width".to_string(),
                     description: "The width of the generated text node".to_string(),
-                    default: Some(DEFAULT_HEIGHT),
+                    default: Some(DEFAULT_WIDTH),
                     min: Some(1),
                     max: None,
                     step: Some(1),
-                    depends_on: None,
+                    depends_on: Vec::new(),
                     display_name: None,
-                    disables: None,
+                    disables: Vec::new(),
                 },
                 PropertyInfo::Integer {
                     name: "height".to_string(),
                     description: "The height of the generated text node".to_string(),
-                    default: Some(DEFAULT_WIDTH),
+                    default: Some(DEFAULT_HEIGHT),
                     min: Some(1),
                     max: None,
                     step: Some(1),
-                    depends_on: None,
+                    depends_on: Vec::new(),
                     display_name: None,
-                    disables: None,
+                    disables: Vec::new(),
                 },
                 PropertyInfo::Boolean {
                     name: "preserve_tabs".to_string(),
                     description: "Use tabs instead of spaces".to_string(),
                     default: Some(false),
-                    depends_on: None,
+                    depends_on: Vec::new(),
                     display_name: None,
-                    disables: Some("spaces_per_tab".to_string()),
+                    disables: vec!["spaces_per_tab".to_string()],
                 },
                 PropertyInfo::Integer {
                     name: "spaces_per_tab".to_string(),
@@ -250,9 +250,9 @@ impl GeneratorExt for FusionGenerator {
                     min: Some(1),
                     max: None,
                     step: Some(1),
-                    depends_on: None,
+                    depends_on: Vec::new(),
                     display_name: None,
-                    disables: None,
+                    disables: Vec::new(),
                 },
             ]),
             syntax: Some("Lua"),
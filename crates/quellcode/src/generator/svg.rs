@@ -1,10 +1,8 @@
-use std::sync::atomic::Ordering;
-
 use crate::generator::GeneratorOptions;
 
 use super::{Generator, GeneratorExt, GeneratorEvent, GeneratorContext, GeneratorInfo, PropertyInfo};
 
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{eyre, Result};
 use svg::{
     node::element::{Rectangle, TSpan, Text},
     Document,
@@ -23,8 +21,13 @@ impl SvgGenerator {
     }
 }
 
-impl Generator for SvgGenerator {
-    fn generate_code(
+/// Shows the light variant of an adaptive SVG unless the viewer prefers a dark color scheme.
+const ADAPTIVE_STYLE: &str = ".dark{display:none}@media (prefers-color-scheme: dark){.light{display:none}.dark{display:inline}}";
+
+impl SvgGenerator {
+    /// Renders the SVG, prefixing its ids so several renders can be combined into one document.
+    #[allow(clippy::too_many_arguments)]
+    fn render(
         &self,
         text: &str,
         theme: &syntect::highlighting::Theme,
@@ -32,6 +35,7 @@ impl Generator for SvgGenerator {
         syntax_set: &syntect::parsing::SyntaxSet,
         options: &GeneratorOptions,
         context: &GeneratorContext,
+        id_prefix: Option<&str>,
     ) -> Result<String> {
         let text_size = options.font_size as usize;
         let font_family = options.font_family.as_str();
@@ -41,10 +45,11 @@ impl Generator for SvgGenerator {
                 .get("bake_font")
                 .and_then(|value| value.clone().try_into().ok())
                 .unwrap_or(true),
+            id_prefix: id_prefix.map(String::from),
             ..Default::default()
         };
 
-        context.event_tx.send(GeneratorEvent::Started)?;
+        context.send(GeneratorEvent::Started)?;
 
         log::debug!(
             "Generating svg with font family {} and font size {}",
@@ -79,11 +84,11 @@ impl Generator for SvgGenerator {
             document = document.add(background_element);
         }
 
-        let _ = context.event_tx.send(GeneratorEvent::progress(1, Some("Generating SVG spans")));
+        let _ = context.send(GeneratorEvent::progress(1, Some("Generating SVG spans")));
 
         for (index, line) in text.lines().enumerate() {
-            if context.cancel.load(Ordering::Relaxed) {
-                context.event_tx.send(GeneratorEvent::Cancelled)?;
+            if context.is_cancelled() {
+                context.send(GeneratorEvent::Cancelled)?;
                 return Ok(String::new());
             }
 
@@ -126,7 +131,7 @@ impl Generator for SvgGenerator {
 
         document = document.set("viewBox", format!("0 0 {} {}", width, height));
 
-        let _ = context.event_tx.send(GeneratorEvent::progress(2, Some("Processing generated SVG")));
+        let _ = context.send(GeneratorEvent::progress(2, Some("Processing generated SVG")));
 
         let document = document.to_string().replace("\n", "");
         let tree = roxmltree::Document::parse_with_options(
@@ -151,6 +156,71 @@ impl Generator for SvgGenerator {
     }
 }
 
+/// Splits a rendered SVG into its opening `<svg>` tag and its content.
+fn split_root(svg: &str) -> Option<(&str, &str)> {
+    let start = svg.find("<svg")?;
+    let content_start = start + svg[start..].find('>')? + 1;
+    let content_end = svg.rfind("</svg>")?;
+
+    Some((&svg[..content_start], &svg[content_start..content_end]))
+}
+
+impl Generator for SvgGenerator {
+    fn generate_code(
+        &self,
+        text: &str,
+        theme: &syntect::highlighting::Theme,
+        syntax: &syntect::parsing::SyntaxReference,
+        syntax_set: &syntect::parsing::SyntaxSet,
+        options: &GeneratorOptions,
+        context: &GeneratorContext,
+    ) -> Result<String> {
+        self.render(text, theme, syntax, syntax_set, options, context, None)
+    }
+
+    fn generate_adaptive(
+        &self,
+        text: &str,
+        light: &syntect::highlighting::Theme,
+        dark: &syntect::highlighting::Theme,
+        syntax: &syntect::parsing::SyntaxReference,
+        syntax_set: &syntect::parsing::SyntaxSet,
+        options: &GeneratorOptions,
+        context: &GeneratorContext,
+    ) -> Option<Result<String>> {
+        let render = |theme, prefix| {
+            self.render(
+                text,
+                theme,
+                syntax,
+                syntax_set,
+                options,
+                context,
+                Some(prefix),
+            )
+        };
+
+        Some((|| {
+            let light = render(light, "light-")?;
+            let dark = render(dark, "dark-")?;
+
+            if context.is_cancelled() {
+                return Ok(String::new());
+            }
+
+            // NOTE: Both variants render the same text with the same font, so they share the
+            // size of the root element.
+            let ((root, light), (_, dark)) = split_root(&light)
+                .zip(split_root(&dark))
+                .ok_or_else(|| eyre!("Generated SVG has no root element"))?;
+
+            Ok(format!(
+                "{root}<style>{ADAPTIVE_STYLE}</style><g class=\"light\">{light}</g><g class=\"dark\">{dark}</g></svg>"
+            ))
+        })())
+    }
+}
+
 impl GeneratorExt for SvgGenerator {
     fn information() -> GeneratorInfo {
         GeneratorInfo {
@@ -162,17 +232,17 @@ impl GeneratorExt for SvgGenerator {
                     name: "include_background".to_string(),
                     description: "Include a background for the code".to_string(),
                     default: Some(true),
-                    depends_on: None,
+                    depends_on: Vec::new(),
                     display_name: None,
-                    disables: None,
+                    disables: Vec::new(),
                 },
                 PropertyInfo::Boolean {
                     name: "bake_font".to_string(),
                     description: "Whether to convert the font to points".to_string(),
                     default: Some(true),
-                    depends_on: None,
+                    depends_on: Vec::new(),
                     display_name: None,
-                    disables: None,
+                    disables: Vec::new(),
                 },
             ]),
             syntax: Some("XML"),
@@ -181,3 +251,68 @@ impl GeneratorExt for SvgGenerator {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+
+    use syntect::{highlighting::ThemeSet, parsing::SyntaxSet};
+
+    use super::*;
+    use crate::generator::GeneratorJobs;
+
+    #[test]
+    fn generate_adaptive_svg() {
+        let (tx, _rx) = channel();
+        let context = GeneratorJobs::new(tx).start();
+        let syntax_set = SyntaxSet::load_defaults_nonewlines();
+        let syntax = syntax_set.find_syntax_by_name("Rust").unwrap();
+        let themes = ThemeSet::load_defaults();
+        let options = GeneratorOptions {
+            font_size: 14.0,
+            font_family: "monospace".to_string(),
+            extra: [("bake_font".to_string(), false.into())].into(),
+            ..Default::default()
+        };
+
+        let svg = SvgGenerator::new()
+            .generate_adaptive(
+                "fn main() {\n    println!(\"hi\");\n}",
+                &themes.themes["InspiredGitHub"],
+                &themes.themes["base16-ocean.dark"],
+                syntax,
+                &syntax_set,
+                &options,
+                &context,
+            )
+            .unwrap()
+            .unwrap();
+
+        let (_, content) = split_root(&svg).unwrap();
+        assert_eq!(svg.matches("<svg").count(), 1);
+        assert!(content.starts_with(&format!("<style>{ADAPTIVE_STYLE}</style>")));
+        assert!(ADAPTIVE_STYLE.contains("@media (prefers-color-scheme: dark)"));
+
+        let document = roxmltree::Document::parse(&svg).unwrap();
+        let group = |class| {
+            document
+                .descendants()
+                .find(|node| node.has_tag_name("g") && node.attribute("class") == Some(class))
+                .unwrap()
+        };
+
+        for (class, background) in [("light", "#ffffff"), ("dark", "#2b303b")] {
+            let group = group(class);
+
+            // NOTE: Each variant keeps its own background, its ids are prefixed so they don't
+            // collide with the other variant's.
+            assert!(group
+                .descendants()
+                .any(|node| node.attribute("fill") == Some(background)));
+            assert!(group
+                .descendants()
+                .filter_map(|node| node.attribute("id"))
+                .all(|id| id.starts_with(&format!("{class}-"))));
+        }
+    }
+}
@@ -1,15 +1,14 @@
 use std::{
     collections::HashMap,
     path::PathBuf,
-    sync::{mpsc::channel, Arc, Mutex},
+    sync::{mpsc::channel, Arc, Mutex, OnceLock},
 };
 
 use color_eyre::{eyre::Result, owo_colors::OwoColorize};
-use log::{debug, warn};
+use log::{debug, error, warn};
 use secrecy::SecretString;
 use serde::Serialize;
 use syntect::{
-    highlighting::{Theme as SnytectTheme, ThemeSet},
     html::{css_for_theme_with_class_style, ClassedHTMLGenerator},
     parsing::SyntaxSet,
     util::LinesWithEndings,
@@ -23,20 +22,40 @@ use ts_rs::TS;
 use crate::{
     dir::config_dir,
     generator::{
-        FusionGenerator, Generator, GeneratorContext, GeneratorExt, GeneratorInfo, SvgGenerator,
+        FusionGenerator, Generator, GeneratorExt, GeneratorInfo, GeneratorJobs, SvgGenerator,
     },
+    settings::Settings,
+    syntax::load_syntaxes,
+    theme::{load_themes, ThemeInfo, ThemeLoadReport, ThemeRegistry},
+    watcher::FileError,
 };
 
 mod app;
 pub mod asset_store;
+pub mod bundled;
+pub mod cli;
 pub mod dir;
+pub mod expression;
 pub mod generator;
+pub mod preset;
 pub mod property;
 pub mod scraping;
-mod settings;
+pub mod settings;
+pub mod snapshot;
+pub mod syntax;
+pub mod theme;
 pub mod util;
-
-use app::generate_code;
+pub mod watcher;
+
+use app::{
+    batch_generate, cancel_generation, delete_preset, detect_syntax, effective_properties,
+    export_preset, generate_code, generator_options, import_presets, install_theme_asset,
+    load_preset, open_source_file, presets, rename_preset, save_preset, save_settings,
+    save_theme_pairs, settings, syntax_load_report, theme_counterpart, theme_load_report,
+    theme_pairs,
+};
+use clap::Parser;
+use cli::{cli_args, Cli};
 
 pub const SYNTECT_PREFIX: &str = "syntect-";
 
@@ -52,8 +71,8 @@ pub enum ThemeFormat {
 impl ThemeFormat {
     pub fn from_extension(ext: &str) -> Option<ThemeFormat> {
         match ext {
-            "sublime-color-scheme" => Some(ThemeFormat::Sublime),
-            "tmTheme" => Some(ThemeFormat::TmTheme),
+            "sublime-color-scheme" | "hidden-color-scheme" => Some(ThemeFormat::Sublime),
+            "tmTheme" | "hidden-tmTheme" => Some(ThemeFormat::TmTheme),
             "json" => Some(ThemeFormat::VsCode),
             _ => None,
         }
@@ -64,18 +83,50 @@ impl ThemeFormat {
             .and_then(|ext| ext.to_str())
             .and_then(ThemeFormat::from_extension)
     }
+
+    /// Whether the theme file at `path` is a hidden variant, which Sublime Text loads but
+    /// doesn't list in its color scheme picker.
+    pub fn is_hidden(path: &std::path::Path) -> bool {
+        path.extension()
+            .is_some_and(|ext| ext == "hidden-color-scheme" || ext == "hidden-tmTheme")
+    }
 }
 
 pub struct AppState {
     pub theme_files: HashMap<PathBuf, ThemeFormat>,
-    pub syntect_themes: ThemeSet,
+    pub theme_report: ThemeLoadReport,
+    pub themes: ThemeRegistry,
     pub syntect_syntaxes: SyntaxSet,
+    /// Syntax files that failed to load, see [load_syntaxes]
+    pub syntax_errors: Vec<FileError>,
     pub generators: Vec<(GeneratorInfo, Arc<dyn Generator>)>,
-    generator_context: GeneratorContext,
+    pub settings: Settings,
+    generator_jobs: GeneratorJobs,
+}
+
+impl AppState {
+    /// Names of all loaded syntaxes, sorted like the syntax list in the frontend.
+    pub fn syntax_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .syntect_syntaxes
+            .syntaxes()
+            .iter()
+            .map(|syntax| syntax.name.to_string())
+            .collect();
+
+        names.sort();
+        names
+    }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let mut cli = Cli::parse();
+
+    if let Some(path) = &cli.check_theme {
+        std::process::exit(cli::check_theme(path));
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_dialog::init())
@@ -104,20 +155,46 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             get_css_for_theme,
             generate_code,
+            cancel_generation,
+            effective_properties,
+            generator_options,
+            presets,
+            load_preset,
+            save_preset,
+            rename_preset,
+            delete_preset,
+            import_presets,
+            export_preset,
+            cli_args,
+            detect_syntax,
+            open_source_file,
+            batch_generate,
+            settings,
+            save_settings,
             generate_html,
             font_families,
             theme_files,
+            theme_load_report,
+            syntax_load_report,
+            theme_pairs,
+            save_theme_pairs,
+            theme_counterpart,
+            install_theme_asset,
             generators,
             syntaxes,
             themes
         ])
         .setup(|app| {
-            let syntax_set = SyntaxSet::load_defaults_nonewlines();
             let scope = app.fs_scope();
             let _ = scope.allow_directory(config_dir(app.app_handle()), true);
 
+            if let Some(file) = &cli.file {
+                let _ = scope.allow_file(file);
+            }
+
             for path in [
                 dir::code_theme_dir(app.app_handle()),
+                dir::store_theme_dir(app.app_handle()),
                 dir::code_syntax_dir(app.app_handle()),
                 dir::config_dir(app.app_handle()),
             ] {
@@ -135,6 +212,13 @@ pub fn run() {
             ];
 
             let theme_files = code_theme_files(app.app_handle());
+            let (syntax_set, syntax_errors) = load_syntaxes(
+                &dir::code_syntax_dir(app.app_handle()),
+                Some(&dir::cache_dir(app.app_handle())),
+            );
+
+            cli.resolve_syntax(&syntax_set);
+            app.manage(cli);
 
             let (tx, rx) = channel();
             let app_handle = app.app_handle().clone();
@@ -146,70 +230,39 @@ pub fn run() {
                 }
             });
 
-            app.manage(Mutex::new(AppState {
-                syntect_themes: load_themes(&theme_files),
+            let (themes, theme_report) =
+                load_themes(&theme_files, &dir::store_theme_dir(app.app_handle()));
+
+            let mut state = AppState {
+                themes,
                 syntect_syntaxes: syntax_set,
+                syntax_errors,
                 theme_files,
+                theme_report,
                 generators,
-                generator_context: GeneratorContext::new(tx.clone()),
-            }));
-
-            Ok(())
-        })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-}
-
-fn load_themes(theme_files: &HashMap<PathBuf, ThemeFormat>) -> ThemeSet {
-    let mut theme_set = ThemeSet::load_defaults();
-
-    for (path, format) in theme_files.iter() {
-        match format {
-            ThemeFormat::VsCode => {
-                let vscode_theme = syntect_vscode::parse_vscode_theme_file(path);
-
-                if let Ok(vscode_theme) = vscode_theme {
-                    let theme_name = vscode_theme
-                        .name
-                        .clone()
-                        .unwrap_or(path.file_stem().unwrap().to_string_lossy().to_string());
-
-                    let theme =
-                        SnytectTheme::try_from(vscode_theme).expect("Failed to parse theme");
+                settings: Settings::default(),
+                generator_jobs: GeneratorJobs::new(tx),
+            };
 
-                    theme_set.themes.insert(theme_name, theme);
-                }
-            }
-            ThemeFormat::Sublime => {
-                let color_scheme = sublime_color_scheme::parse_color_scheme_file(path);
+            state.settings = Settings::load_or_create(&dir::config_file(app.app_handle()))
+                .unwrap_or_else(|err| {
+                    error!("Failed to load settings, using defaults: {err}");
+                    Settings::default()
+                });
 
-                if let Ok(color_scheme) = color_scheme {
-                    let theme_name = color_scheme
-                        .name
-                        .clone()
-                        .unwrap_or(path.file_stem().unwrap().to_string_lossy().to_string());
+            let (theme_keys, syntax_names) = (state.themes.keys(), state.syntax_names());
+            state.settings.validate(&theme_keys, &syntax_names);
 
-                    let theme =
-                        SnytectTheme::try_from(color_scheme).expect("Failed to parse theme");
+            app.manage(Mutex::new(state));
 
-                    theme_set.themes.insert(theme_name, theme);
-                }
+            if let Err(err) = watcher::watch(app.app_handle()) {
+                error!("Failed to watch for theme, syntax and settings changes: {err}");
             }
-            ThemeFormat::TmTheme => {
-                let theme = ThemeSet::get_theme(path);
-                if let Ok(theme) = theme {
-                    let theme_name = theme
-                        .clone()
-                        .name
-                        .unwrap_or(path.file_stem().unwrap().to_string_lossy().to_string());
-
-                    theme_set.themes.insert(theme_name, theme);
-                }
-            }
-        }
-    }
 
-    theme_set
+            Ok(())
+        })
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
 }
 
 #[tauri::command]
@@ -275,9 +328,11 @@ fn syntaxes(state: State<Mutex<AppState>>) -> Vec<String> {
 }
 
 #[tauri::command]
-fn get_css_for_theme(state: State<Mutex<AppState>>, theme: String) -> String {
-    let theme_set = &state.lock().expect("Failed to lock state").syntect_themes;
-    let theme = theme_set.themes.get(&theme).expect("Failed to get theme");
+fn get_css_for_theme(state: State<Mutex<AppState>>, theme: String) -> Result<String, String> {
+    let themes = &state.lock().expect("Failed to lock state").themes;
+    let theme = themes
+        .theme(&theme)
+        .ok_or(format!("Could not find theme \"{}\"", theme))?;
 
     css_for_theme_with_class_style(
         theme,
@@ -285,19 +340,12 @@ fn get_css_for_theme(state: State<Mutex<AppState>>, theme: String) -> String {
             prefix: SYNTECT_PREFIX,
         },
     )
-    .expect("Failed to generate css")
+    .map_err(|err| err.to_string())
 }
 
 #[tauri::command]
-fn themes(state: State<Mutex<AppState>>) -> Vec<String> {
-    state
-        .lock()
-        .expect("Failed to lock state")
-        .syntect_themes
-        .themes
-        .iter()
-        .map(|t| t.0.to_string())
-        .collect()
+fn themes(state: State<Mutex<AppState>>) -> Vec<ThemeInfo> {
+    state.lock().expect("Failed to lock state").themes.infos()
 }
 
 #[derive(Debug, TS, Serialize, Clone)]
@@ -309,6 +357,17 @@ pub struct FontFamily {
 
 #[tauri::command]
 fn font_families() -> Vec<FontFamily> {
+    installed_font_families().to_vec()
+}
+
+/// Font families installed on the system, loaded on first use.
+pub fn installed_font_families() -> &'static [FontFamily] {
+    static FAMILIES: OnceLock<Vec<FontFamily>> = OnceLock::new();
+
+    FAMILIES.get_or_init(load_font_families)
+}
+
+fn load_font_families() -> Vec<FontFamily> {
     let mut db = usvg::fontdb::Database::new();
     db.load_system_fonts();
 
@@ -354,22 +413,29 @@ pub fn github_token() -> Result<Option<SecretString>> {
 }
 
 pub fn code_theme_files(app_handle: &tauri::AppHandle) -> HashMap<PathBuf, ThemeFormat> {
-    let themes_dir = dir::code_theme_dir(app_handle);
-
-    themes_dir
-        .read_dir()
-        .expect("Failed to read themes dir")
-        .filter_map(|entry| {
-            entry.ok().and_then(|entry| {
-                let path = entry.path();
-                if path.is_file() {
-                    ThemeFormat::from_path(&path).map(|format| (path, format))
-                } else {
-                    None
-                }
-            })
+    [
+        dir::code_theme_dir(app_handle),
+        dir::store_theme_dir(app_handle),
+    ]
+    .iter()
+    .filter_map(|themes_dir| {
+        themes_dir
+            .read_dir()
+            .inspect_err(|err| error!("Failed to read {}: {err}", themes_dir.display()))
+            .ok()
+    })
+    .flatten()
+    .filter_map(|entry| {
+        entry.ok().and_then(|entry| {
+            let path = entry.path();
+            if path.is_file() {
+                ThemeFormat::from_path(&path).map(|format| (path, format))
+            } else {
+                None
+            }
         })
-        .collect()
+    })
+    .collect()
 }
 
 #[cfg(test)]
@@ -0,0 +1,427 @@
+//! Named generator presets.
+//!
+//! Presets are stored as tables in a single TOML file, keyed by their name:
+//!
+//! ```toml
+//! # Used for every blog post
+//! [blog]
+//! generator = "SVG"
+//! theme = "Nord"
+//! font_family = "Fira Code"
+//! font_size = 14.0
+//!
+//! [blog.extra]
+//! include_background = true
+//! ```
+//!
+//! The file is edited with [toml_edit] so comments and formatting written by hand survive
+//! saving, renaming and deleting presets.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use toml_edit::{DocumentMut, InlineTable, Item, Table, Value};
+use ts_rs::TS;
+
+use crate::{
+    generator::GeneratorOptions,
+    property::{LineRange, PropertyValue},
+    util::set_toml_value,
+};
+
+pub const PRESETS_FILE: &str = "presets.toml";
+
+#[derive(Debug, Error)]
+pub enum PresetError {
+    #[error("Preset \"{0}\" does not exist")]
+    NotFound(String),
+    #[error("Preset \"{0}\" already exists")]
+    AlreadyExists(String),
+    #[error("Invalid preset name \"{0}\"")]
+    InvalidName(String),
+    #[error("Preset \"{preset}\" is missing \"{key}\" or it has the wrong type")]
+    InvalidField { preset: String, key: String },
+    #[error("Failed to access presets: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to parse presets: {0}")]
+    Parse(#[from] toml_edit::TomlError),
+}
+
+/// A saved combination of generator, theme, font and generator properties.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Preset {
+    pub name: String,
+    pub generator: String,
+    pub theme: String,
+    #[serde(default)]
+    #[ts(optional)]
+    pub syntax: Option<String>,
+    pub font_family: String,
+    pub font_size: f32,
+    #[serde(default)]
+    #[ts(optional)]
+    pub font_size_expression: Option<String>,
+    #[serde(default)]
+    pub extra: BTreeMap<String, PropertyValue>,
+}
+
+impl Preset {
+    pub fn options(&self) -> GeneratorOptions {
+        GeneratorOptions {
+            font_size: self.font_size,
+            font_size_expression: self.font_size_expression.clone(),
+            font_family: self.font_family.clone(),
+            extra: self.extra.clone(),
+        }
+    }
+
+    fn from_table(name: &str, table: &Table) -> Result<Preset, PresetError> {
+        let invalid = |key: &str| PresetError::InvalidField {
+            preset: name.to_string(),
+            key: key.to_string(),
+        };
+        let string = |key: &str| {
+            table
+                .get(key)
+                .and_then(Item::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| invalid(key))
+        };
+        let optional_string = |key: &str| match table.get(key) {
+            None => Ok(None),
+            Some(item) => item
+                .as_str()
+                .map(|s| Some(s.to_string()))
+                .ok_or_else(|| invalid(key)),
+        };
+
+        let font_size = table
+            .get("font_size")
+            .and_then(|item| {
+                item.as_float()
+                    .or_else(|| item.as_integer().map(|int| int as f64))
+            })
+            .ok_or_else(|| invalid("font_size"))?;
+
+        let extra = match table.get("extra") {
+            None => BTreeMap::new(),
+            Some(item) => item
+                .as_table_like()
+                .ok_or_else(|| invalid("extra"))?
+                .iter()
+                .map(|(key, item)| {
+                    item.as_value()
+                        .and_then(from_toml_value)
+                        .map(|value| (key.to_string(), value))
+                        .ok_or_else(|| invalid(&format!("extra.{key}")))
+                })
+                .collect::<Result<_, _>>()?,
+        };
+
+        Ok(Preset {
+            name: name.to_string(),
+            generator: string("generator")?,
+            theme: string("theme")?,
+            syntax: optional_string("syntax")?,
+            font_family: string("font_family")?,
+            font_size: font_size as f32,
+            font_size_expression: optional_string("font_size_expression")?,
+            extra,
+        })
+    }
+
+    /// Writes the preset into `table`, keeping the comments and formatting of existing keys.
+    fn write_table(&self, table: &mut Table) {
+        set_toml_value(table, "generator", self.generator.as_str().into());
+        set_toml_value(table, "theme", self.theme.as_str().into());
+        set_optional_value(table, "syntax", self.syntax.as_deref().map(Value::from));
+        set_toml_value(table, "font_family", self.font_family.as_str().into());
+        set_toml_value(table, "font_size", (self.font_size as f64).into());
+        set_optional_value(
+            table,
+            "font_size_expression",
+            self.font_size_expression.as_deref().map(Value::from),
+        );
+
+        if self.extra.is_empty() {
+            table.remove("extra");
+            return;
+        }
+
+        if !table.get("extra").is_some_and(Item::is_table) {
+            table.insert("extra", Item::Table(Table::new()));
+        }
+
+        let extra = table["extra"].as_table_mut().expect("extra is a table");
+        let stale: Vec<String> = extra
+            .iter()
+            .map(|(key, _)| key.to_string())
+            .filter(|key| !self.extra.contains_key(key))
+            .collect();
+
+        for key in stale {
+            extra.remove(&key);
+        }
+
+        for (key, property) in &self.extra {
+            set_toml_value(extra, key, to_toml_value(property));
+        }
+    }
+}
+
+/// The presets file loaded into memory.
+#[derive(Debug)]
+pub struct PresetStore {
+    path: PathBuf,
+    document: DocumentMut,
+}
+
+impl PresetStore {
+    /// Opens the presets file at `path`, a missing file is treated as empty.
+    pub fn open(path: impl Into<PathBuf>) -> Result<PresetStore, PresetError> {
+        let path = path.into();
+        let document = match std::fs::read_to_string(&path) {
+            Ok(content) => content.parse()?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => DocumentMut::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(PresetStore { path, document })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.document
+            .iter()
+            .filter(|(_, item)| item.is_table())
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+
+    pub fn list(&self) -> Result<Vec<Preset>, PresetError> {
+        self.names().iter().map(|name| self.get(name)).collect()
+    }
+
+    pub fn get(&self, name: &str) -> Result<Preset, PresetError> {
+        let table = self
+            .document
+            .get(name)
+            .and_then(Item::as_table)
+            .ok_or_else(|| PresetError::NotFound(name.to_string()))?;
+
+        Preset::from_table(name, table)
+    }
+
+    /// Inserts or updates a preset and writes the file.
+    pub fn save(&mut self, preset: &Preset) -> Result<(), PresetError> {
+        validate_name(&preset.name)?;
+        self.insert(preset);
+        self.write()
+    }
+
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<(), PresetError> {
+        validate_name(to)?;
+
+        if from == to {
+            return Ok(());
+        }
+
+        if self.document.contains_key(to) {
+            return Err(PresetError::AlreadyExists(to.to_string()));
+        }
+
+        if !self.document.get(from).is_some_and(Item::is_table) {
+            return Err(PresetError::NotFound(from.to_string()));
+        }
+
+        let item = self.document.remove(from).expect("preset exists");
+        self.document.insert(to, item);
+        self.write()
+    }
+
+    pub fn delete(&mut self, name: &str) -> Result<(), PresetError> {
+        self.document
+            .remove(name)
+            .ok_or_else(|| PresetError::NotFound(name.to_string()))?;
+
+        self.write()
+    }
+
+    /// Imports every preset of another presets file, replacing presets with the same name.
+    ///
+    /// Returns the names of the imported presets.
+    pub fn import(&mut self, path: &Path) -> Result<Vec<String>, PresetError> {
+        let content = std::fs::read_to_string(path)?;
+        let other = PresetStore {
+            path: path.to_path_buf(),
+            document: content.parse()?,
+        };
+        let presets = other.list()?;
+
+        for preset in &presets {
+            self.insert(preset);
+        }
+
+        self.write()?;
+
+        Ok(presets.into_iter().map(|preset| preset.name).collect())
+    }
+
+    /// Writes a single preset to a standalone presets file that can be imported elsewhere.
+    pub fn export(&self, name: &str, path: &Path) -> Result<(), PresetError> {
+        let item = self
+            .document
+            .get(name)
+            .filter(|item| item.is_table())
+            .ok_or_else(|| PresetError::NotFound(name.to_string()))?;
+
+        let mut document = DocumentMut::new();
+        document.insert(name, item.clone());
+
+        std::fs::write(path, document.to_string())?;
+
+        Ok(())
+    }
+
+    fn insert(&mut self, preset: &Preset) {
+        if !self.document.get(&preset.name).is_some_and(Item::is_table) {
+            self.document
+                .insert(&preset.name, Item::Table(Table::new()));
+        }
+
+        let table = self.document[preset.name.as_str()]
+            .as_table_mut()
+            .expect("preset is a table");
+
+        preset.write_table(table);
+    }
+
+    fn write(&self) -> Result<(), PresetError> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(&self.path, self.document.to_string())?;
+
+        Ok(())
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), PresetError> {
+    if name.trim().is_empty() || name.trim() != name {
+        return Err(PresetError::InvalidName(name.to_string()));
+    }
+
+    Ok(())
+}
+
+fn set_optional_value(table: &mut Table, key: &str, new: Option<Value>) {
+    match new {
+        Some(new) => set_toml_value(table, key, new),
+        None => {
+            table.remove(key);
+        }
+    }
+}
+
+/// Colors and line ranges are stored as strings, property validation parses them again.
+fn to_toml_value(property: &PropertyValue) -> Value {
+    match property {
+        PropertyValue::String(string) => string.as_str().into(),
+        PropertyValue::Int(int) => (*int as i64).into(),
+        PropertyValue::Float(float) => (*float).into(),
+        PropertyValue::Bool(bool) => (*bool).into(),
+        PropertyValue::Color(color) => color.to_hex(true).into(),
+        PropertyValue::LineRanges(ranges) => ranges
+            .iter()
+            .map(LineRange::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+            .into(),
+        PropertyValue::Expression { expression } => {
+            let mut table = InlineTable::new();
+            table.insert("expression", expression.as_str().into());
+            Value::InlineTable(table)
+        }
+    }
+}
+
+fn from_toml_value(value: &Value) -> Option<PropertyValue> {
+    match value {
+        Value::String(string) => Some(PropertyValue::String(string.value().clone())),
+        Value::Integer(int) => i32::try_from(*int.value()).ok().map(PropertyValue::Int),
+        Value::Float(float) => Some(PropertyValue::Float(*float.value())),
+        Value::Boolean(bool) => Some(PropertyValue::Bool(*bool.value())),
+        Value::InlineTable(table) => {
+            table
+                .get("expression")
+                .and_then(Value::as_str)
+                .map(|expression| PropertyValue::Expression {
+                    expression: expression.to_string(),
+                })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_presets_keeping_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("presets.toml");
+        std::fs::write(
+            &path,
+            r#"# Used for every blog post
+[blog]
+generator = "SVG" # always SVG
+theme = "Nord"
+font_family = "Fira Code"
+font_size = 14.0
+"#,
+        )
+        .unwrap();
+
+        let mut store = PresetStore::open(&path).unwrap();
+        let mut preset = store.get("blog").unwrap();
+        assert_eq!(preset.generator, "SVG");
+
+        preset.theme = "Monokai".to_string();
+        preset.extra = BTreeMap::from([
+            ("include_background".to_string(), true.into()),
+            (
+                "height".to_string(),
+                PropertyValue::Expression {
+                    expression: "lines * 20".to_string(),
+                },
+            ),
+        ]);
+        store.save(&preset).unwrap();
+        store.rename("blog", "posts").unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let store = PresetStore::open(&path).unwrap();
+
+        assert!(content.contains("# Used for every blog post"));
+        assert!(content.contains("# always SVG"));
+        assert!(matches!(store.get("blog"), Err(PresetError::NotFound(_))));
+        assert_eq!(
+            store.get("posts").unwrap(),
+            Preset {
+                name: "posts".to_string(),
+                ..preset
+            }
+        );
+    }
+}
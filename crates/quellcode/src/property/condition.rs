@@ -0,0 +1,290 @@
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    str::FromStr,
+};
+use ts_rs::TS;
+
+use super::{PropertyError, PropertyInfo, PropertyValue};
+
+/// A condition on the value of another property.
+///
+/// Can be parsed from strings such as `"include_background"`, `"!preserve_tabs"`,
+/// `"frame_style == macos"` or `"frame_style != none"`.
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase", tag = "kind")]
+#[ts(export)]
+pub enum PropertyCondition {
+    /// The property is set to a truthy value
+    Enabled { property: String },
+    /// The property is set to a falsy value or is inactive
+    Disabled { property: String },
+    Equals {
+        property: String,
+        value: PropertyValue,
+    },
+    NotEquals {
+        property: String,
+        value: PropertyValue,
+    },
+}
+
+impl PropertyCondition {
+    pub fn property(&self) -> &str {
+        match self {
+            PropertyCondition::Enabled { property }
+            | PropertyCondition::Disabled { property }
+            | PropertyCondition::Equals { property, .. }
+            | PropertyCondition::NotEquals { property, .. } => property,
+        }
+    }
+
+    /// Checks the condition against the value of the referenced property, `None` if the
+    /// property has no value or is inactive.
+    pub fn is_met(&self, value: Option<&PropertyValue>) -> bool {
+        match self {
+            PropertyCondition::Enabled { .. } => value.is_some_and(is_truthy),
+            PropertyCondition::Disabled { .. } => !value.is_some_and(is_truthy),
+            PropertyCondition::Equals {
+                value: expected, ..
+            } => value.is_some_and(|value| loosely_equals(value, expected)),
+            PropertyCondition::NotEquals {
+                value: expected, ..
+            } => !value.is_some_and(|value| loosely_equals(value, expected)),
+        }
+    }
+}
+
+impl FromStr for PropertyCondition {
+    type Err = PropertyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || PropertyError::InvalidCondition(s.to_string());
+        let s = s.trim();
+
+        let (property, operator, value) = if let Some((property, value)) = s.split_once("!=") {
+            (property.trim(), "!=", Some(value.trim()))
+        } else if let Some((property, value)) = s.split_once("==") {
+            (property.trim(), "==", Some(value.trim()))
+        } else if let Some(property) = s.strip_prefix('!') {
+            (property.trim(), "!", None)
+        } else {
+            (s, "", None)
+        };
+
+        if property.is_empty()
+            || !property
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(invalid());
+        }
+
+        let property = property.to_string();
+
+        Ok(match (operator, value) {
+            ("==", Some(value)) if !value.is_empty() => PropertyCondition::Equals {
+                property,
+                value: parse_literal(value),
+            },
+            ("!=", Some(value)) if !value.is_empty() => PropertyCondition::NotEquals {
+                property,
+                value: parse_literal(value),
+            },
+            ("!", None) => PropertyCondition::Disabled { property },
+            ("", None) => PropertyCondition::Enabled { property },
+            _ => return Err(invalid()),
+        })
+    }
+}
+
+fn parse_literal(value: &str) -> PropertyValue {
+    if let Some(string) = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    {
+        return PropertyValue::String(string.to_string());
+    }
+
+    if let Ok(bool) = value.parse::<bool>() {
+        PropertyValue::Bool(bool)
+    } else if let Ok(int) = value.parse::<i32>() {
+        PropertyValue::Int(int)
+    } else if let Ok(float) = value.parse::<f64>() {
+        PropertyValue::Float(float)
+    } else {
+        PropertyValue::String(value.to_string())
+    }
+}
+
+fn is_truthy(value: &PropertyValue) -> bool {
+    match value {
+        PropertyValue::Bool(bool) => *bool,
+        PropertyValue::Int(int) => *int != 0,
+        PropertyValue::Float(float) => *float != 0.0,
+        PropertyValue::String(string) => !string.is_empty(),
+        PropertyValue::LineRanges(ranges) => !ranges.is_empty(),
+        PropertyValue::Color(_) | PropertyValue::Expression { .. } => true,
+    }
+}
+
+fn loosely_equals(value: &PropertyValue, expected: &PropertyValue) -> bool {
+    match (value, expected) {
+        (PropertyValue::Int(int), PropertyValue::Float(float))
+        | (PropertyValue::Float(float), PropertyValue::Int(int)) => *int as f64 == *float,
+        (value, expected) => value == expected,
+    }
+}
+
+/// Works out which properties are active for the given values.
+///
+/// A property is active when all of its `depends_on` conditions are met and no active,
+/// truthy property lists it in `disables`. Missing values fall back to the declared defaults.
+pub fn active_properties<'a>(
+    properties: &'a [PropertyInfo],
+    values: &BTreeMap<String, PropertyValue>,
+) -> BTreeSet<&'a str> {
+    let value_of = |name: &str| {
+        values.get(name).cloned().or_else(|| {
+            properties
+                .iter()
+                .find(|property| property.name() == name)
+                .and_then(PropertyInfo::default_value)
+        })
+    };
+
+    let mut active: BTreeSet<&str> = properties.iter().map(PropertyInfo::name).collect();
+
+    // NOTE: Deactivating a property can re-enable the ones it disabled, so iterate until the
+    // set settles, bounded in case the dependencies form a cycle.
+    for _ in 0..=properties.len() {
+        let next: BTreeSet<&str> = properties
+            .iter()
+            .filter(|property| {
+                let dependencies_met = property.depends_on().iter().all(|condition| {
+                    let value = active
+                        .contains(condition.property())
+                        .then(|| value_of(condition.property()))
+                        .flatten();
+
+                    condition.is_met(value.as_ref())
+                });
+
+                let disabled = properties.iter().any(|other| {
+                    active.contains(other.name())
+                        && other.disables().iter().any(|name| name == property.name())
+                        && value_of(other.name()).is_some_and(|value| is_truthy(&value))
+                });
+
+                dependencies_met && !disabled
+            })
+            .map(PropertyInfo::name)
+            .collect();
+
+        if next == active {
+            break;
+        }
+
+        active = next;
+    }
+
+    active
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn boolean(name: &str, default: bool, disables: &[&str]) -> PropertyInfo {
+        PropertyInfo::Boolean {
+            name: name.to_string(),
+            description: String::new(),
+            default: Some(default),
+            depends_on: Vec::new(),
+            display_name: None,
+            disables: disables.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn parse_conditions() {
+        let conditions = [
+            (
+                "include_background",
+                PropertyCondition::Enabled {
+                    property: "include_background".to_string(),
+                },
+            ),
+            (
+                "!preserve_tabs",
+                PropertyCondition::Disabled {
+                    property: "preserve_tabs".to_string(),
+                },
+            ),
+            (
+                "frame_style == macos",
+                PropertyCondition::Equals {
+                    property: "frame_style".to_string(),
+                    value: "macos".into(),
+                },
+            ),
+            (
+                "padding != 0",
+                PropertyCondition::NotEquals {
+                    property: "padding".to_string(),
+                    value: 0.into(),
+                },
+            ),
+        ];
+
+        for (string, expected) in conditions {
+            assert_eq!(PropertyCondition::from_str(string).unwrap(), expected);
+        }
+
+        assert!(PropertyCondition::from_str("== macos").is_err());
+        assert!(PropertyCondition::from_str("frame_style ==").is_err());
+    }
+
+    #[test]
+    fn resolve_active_properties() {
+        let properties = [
+            PropertyInfo::Enum {
+                name: "frame_style".to_string(),
+                description: String::new(),
+                default: Some("none".to_string()),
+                options: vec!["none".to_string(), "macos".to_string()],
+                depends_on: Vec::new(),
+                display_name: None,
+                disables: Vec::new(),
+            },
+            PropertyInfo::String {
+                name: "window_title".to_string(),
+                description: String::new(),
+                default: None,
+                sub_type: None,
+                display_name: None,
+                depends_on: vec!["frame_style == macos".parse().unwrap()],
+                disables: Vec::new(),
+            },
+            boolean("preserve_tabs", false, &["spaces_per_tab", "tab_width"]),
+            boolean("spaces_per_tab", true, &[]),
+            boolean("tab_width", true, &[]),
+        ];
+
+        let active = active_properties(&properties, &BTreeMap::new());
+        assert!(!active.contains("window_title"));
+        assert!(active.contains("spaces_per_tab"));
+
+        let active = active_properties(
+            &properties,
+            &BTreeMap::from([
+                ("frame_style".to_string(), "macos".into()),
+                ("preserve_tabs".to_string(), true.into()),
+            ]),
+        );
+
+        assert!(active.contains("window_title"));
+        assert!(!active.contains("spaces_per_tab"));
+        assert!(!active.contains("tab_width"));
+    }
+}
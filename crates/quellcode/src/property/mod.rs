@@ -0,0 +1,901 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+    path::Path,
+    str::FromStr,
+};
+use thiserror::Error;
+use ts_rs::TS;
+
+mod condition;
+
+/// CSS generic font families, resolved to an installed family when rendering.
+const GENERIC_FONT_FAMILIES: &[&str] = &["serif", "sans-serif", "cursive", "fantasy", "monospace"];
+
+pub use condition::*;
+
+#[derive(Debug, Clone, PartialEq, Error, Serialize, TS)]
+#[serde(rename_all = "camelCase", tag = "kind", content = "details")]
+#[ts(export)]
+pub enum PropertyError {
+    #[error("Unknown property \"{0}\"")]
+    UnknownProperty(String),
+    #[error("Invalid value for property \"{property}\": {source}")]
+    InvalidProperty {
+        property: String,
+        source: Box<PropertyError>,
+    },
+    #[error("Cannot assign value to property")]
+    InvalidValueType,
+    #[error("{value} is less than the minimum of {min}")]
+    BelowMinimum { value: f64, min: f64 },
+    #[error("{value} is greater than the maximum of {max}")]
+    AboveMaximum { value: f64, max: f64 },
+    #[error("{value} is not a multiple of the step {step}")]
+    InvalidStep { value: f64, step: f64 },
+    #[error("\"{0}\" is not one of the allowed options")]
+    InvalidOption(String),
+    #[error("Invalid color \"{0}\"")]
+    InvalidColor(String),
+    #[error("Invalid line range \"{0}\"")]
+    InvalidLineRange(String),
+    #[error("File \"{0}\" does not have an allowed extension")]
+    InvalidFileExtension(String),
+    #[error("Font family \"{0}\" is not installed")]
+    UnknownFontFamily(String),
+    #[error("Failed to evaluate \"{expression}\": {message}")]
+    InvalidExpression { expression: String, message: String },
+    #[error("Invalid condition \"{0}\"")]
+    InvalidCondition(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[serde(untagged)]
+pub enum PropertyValue {
+    String(String),
+    Int(i32),
+    Float(f64),
+    Bool(bool),
+    Color(PropertyColor),
+    LineRanges(Vec<LineRange>),
+    /// An expression evaluated before generation, see [crate::expression]
+    Expression {
+        expression: String,
+    },
+}
+
+/// A RGBA color used by [PropertyInfo::Color] properties.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PropertyColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl PropertyColor {
+    pub fn new(r: u8, g: u8, b: u8, a: u8) -> PropertyColor {
+        PropertyColor { r, g, b, a }
+    }
+
+    /// Formats the color as `#rrggbb`, or `#rrggbbaa` if `alpha` is set.
+    pub fn to_hex(&self, alpha: bool) -> String {
+        if alpha {
+            format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, self.a)
+        } else {
+            format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+        }
+    }
+}
+
+impl FromStr for PropertyColor {
+    type Err = PropertyError;
+
+    /// Parses a `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa` hex color.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || PropertyError::InvalidColor(s.to_string());
+        let hex = s.trim().strip_prefix('#').ok_or_else(invalid)?;
+
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let channel = |range: std::ops::Range<usize>| {
+            u8::from_str_radix(&hex[range], 16).map_err(|_| invalid())
+        };
+
+        let short = |index: usize| channel(index..index + 1).map(|value| value * 17);
+
+        match hex.len() {
+            3 => Ok(PropertyColor::new(short(0)?, short(1)?, short(2)?, 255)),
+            4 => Ok(PropertyColor::new(
+                short(0)?,
+                short(1)?,
+                short(2)?,
+                short(3)?,
+            )),
+            6 => Ok(PropertyColor::new(
+                channel(0..2)?,
+                channel(2..4)?,
+                channel(4..6)?,
+                255,
+            )),
+            8 => Ok(PropertyColor::new(
+                channel(0..2)?,
+                channel(2..4)?,
+                channel(4..6)?,
+                channel(6..8)?,
+            )),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl From<syntect::highlighting::Color> for PropertyColor {
+    fn from(color: syntect::highlighting::Color) -> Self {
+        PropertyColor::new(color.r, color.g, color.b, color.a)
+    }
+}
+
+impl From<PropertyColor> for syntect::highlighting::Color {
+    fn from(color: PropertyColor) -> Self {
+        syntect::highlighting::Color {
+            r: color.r,
+            g: color.g,
+            b: color.b,
+            a: color.a,
+        }
+    }
+}
+
+/// An inclusive, one based range of lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LineRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl LineRange {
+    pub fn new(start: u32, end: u32) -> LineRange {
+        LineRange { start, end }
+    }
+
+    pub fn contains(&self, line: u32) -> bool {
+        (self.start..=self.end).contains(&line)
+    }
+
+    /// Parses a comma separated list of ranges, e.g. `1-4, 8, 10-12`.
+    pub fn parse_list(s: &str) -> Result<Vec<LineRange>, PropertyError> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|range| !range.is_empty())
+            .map(LineRange::from_str)
+            .collect()
+    }
+
+    fn validate(&self) -> Result<(), PropertyError> {
+        if self.start == 0 || self.start > self.end {
+            return Err(PropertyError::InvalidLineRange(self.to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for LineRange {
+    type Err = PropertyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || PropertyError::InvalidLineRange(s.to_string());
+        let parse = |value: &str| value.trim().parse::<u32>().map_err(|_| invalid());
+
+        let range = match s.split_once('-') {
+            Some((start, end)) => LineRange::new(parse(start)?, parse(end)?),
+            None => {
+                let line = parse(s)?;
+                LineRange::new(line, line)
+            }
+        };
+
+        range.validate()?;
+        Ok(range)
+    }
+}
+
+impl Display for LineRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum StringPropertySubtype {
+    Path,
+    Template,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase", tag = "kind")]
+#[ts(export)]
+pub enum PropertyInfo {
+    String {
+        name: String,
+        description: String,
+        default: Option<String>,
+        sub_type: Option<StringPropertySubtype>,
+        display_name: Option<String>,
+        depends_on: Vec<PropertyCondition>,
+        disables: Vec<String>,
+    },
+
+    Integer {
+        name: String,
+        description: String,
+        default: Option<i32>,
+        min: Option<i32>,
+        max: Option<i32>,
+        step: Option<i32>,
+        depends_on: Vec<PropertyCondition>,
+        display_name: Option<String>,
+        disables: Vec<String>,
+    },
+
+    Float {
+        name: String,
+        description: String,
+        default: Option<f64>,
+        min: Option<f64>,
+        max: Option<f64>,
+        step: Option<f64>,
+        depends_on: Vec<PropertyCondition>,
+        display_name: Option<String>,
+        disables: Vec<String>,
+    },
+
+    Boolean {
+        name: String,
+        description: String,
+        default: Option<bool>,
+        depends_on: Vec<PropertyCondition>,
+        display_name: Option<String>,
+        disables: Vec<String>,
+    },
+
+    /// A choice between a fixed set of options
+    Enum {
+        name: String,
+        description: String,
+        default: Option<String>,
+        options: Vec<String>,
+        depends_on: Vec<PropertyCondition>,
+        display_name: Option<String>,
+        disables: Vec<String>,
+    },
+
+    Color {
+        name: String,
+        description: String,
+        default: Option<PropertyColor>,
+        /// Whether the alpha channel can be edited
+        alpha: bool,
+        depends_on: Vec<PropertyCondition>,
+        display_name: Option<String>,
+        disables: Vec<String>,
+    },
+
+    /// A font family, chosen from the families returned by the `font_families` command
+    Font {
+        name: String,
+        description: String,
+        default: Option<String>,
+        /// Only list monospaced families
+        monospace: bool,
+        depends_on: Vec<PropertyCondition>,
+        display_name: Option<String>,
+        disables: Vec<String>,
+    },
+
+    LineRanges {
+        name: String,
+        description: String,
+        default: Option<Vec<LineRange>>,
+        depends_on: Vec<PropertyCondition>,
+        display_name: Option<String>,
+        disables: Vec<String>,
+    },
+
+    File {
+        name: String,
+        description: String,
+        default: Option<String>,
+        /// Allowed file extensions without the leading dot, any file is allowed if empty
+        extensions: Vec<String>,
+        depends_on: Vec<PropertyCondition>,
+        display_name: Option<String>,
+        disables: Vec<String>,
+    },
+}
+
+impl PropertyInfo {
+    pub fn name(&self) -> &str {
+        match self {
+            PropertyInfo::String { name, .. } => name,
+            PropertyInfo::Integer { name, .. } => name,
+            PropertyInfo::Float { name, .. } => name,
+            PropertyInfo::Boolean { name, .. } => name,
+            PropertyInfo::Enum { name, .. } => name,
+            PropertyInfo::Color { name, .. } => name,
+            PropertyInfo::Font { name, .. } => name,
+            PropertyInfo::LineRanges { name, .. } => name,
+            PropertyInfo::File { name, .. } => name,
+        }
+    }
+
+    /// Conditions that all have to be met for the property to be active.
+    pub fn depends_on(&self) -> &[PropertyCondition] {
+        match self {
+            PropertyInfo::String { depends_on, .. }
+            | PropertyInfo::Integer { depends_on, .. }
+            | PropertyInfo::Float { depends_on, .. }
+            | PropertyInfo::Boolean { depends_on, .. }
+            | PropertyInfo::Enum { depends_on, .. }
+            | PropertyInfo::Color { depends_on, .. }
+            | PropertyInfo::Font { depends_on, .. }
+            | PropertyInfo::LineRanges { depends_on, .. }
+            | PropertyInfo::File { depends_on, .. } => depends_on,
+        }
+    }
+
+    /// Properties that are deactivated while this property has a truthy value.
+    pub fn disables(&self) -> &[String] {
+        match self {
+            PropertyInfo::String { disables, .. }
+            | PropertyInfo::Integer { disables, .. }
+            | PropertyInfo::Float { disables, .. }
+            | PropertyInfo::Boolean { disables, .. }
+            | PropertyInfo::Enum { disables, .. }
+            | PropertyInfo::Color { disables, .. }
+            | PropertyInfo::Font { disables, .. }
+            | PropertyInfo::LineRanges { disables, .. }
+            | PropertyInfo::File { disables, .. } => disables,
+        }
+    }
+
+    /// Checks that `value` can be assigned to this property.
+    ///
+    /// Returns the value converted to the property's type, e.g. a hex string becomes a
+    /// [PropertyValue::Color] and `"1-4, 8"` becomes [PropertyValue::LineRanges].
+    pub fn validate(&self, value: PropertyValue) -> Result<PropertyValue, PropertyError> {
+        match self {
+            PropertyInfo::String { .. } => match value {
+                PropertyValue::String(_) => Ok(value),
+                _ => Err(PropertyError::InvalidValueType),
+            },
+            PropertyInfo::Integer { min, max, step, .. } => {
+                let int = match value {
+                    PropertyValue::Int(int) => int,
+                    PropertyValue::Float(float) if float.fract() == 0.0 => float as i32,
+                    _ => return Err(PropertyError::InvalidValueType),
+                };
+
+                check_range(
+                    int as f64,
+                    min.map(f64::from),
+                    max.map(f64::from),
+                    step.map(f64::from),
+                )?;
+
+                Ok(PropertyValue::Int(int))
+            }
+            PropertyInfo::Float { min, max, step, .. } => {
+                let float: f64 = value.try_into()?;
+                check_range(float, *min, *max, *step)?;
+
+                Ok(PropertyValue::Float(float))
+            }
+            PropertyInfo::Boolean { .. } => match value {
+                PropertyValue::Bool(_) => Ok(value),
+                _ => Err(PropertyError::InvalidValueType),
+            },
+            PropertyInfo::Enum { options, .. } => match value {
+                PropertyValue::String(option) if options.contains(&option) => {
+                    Ok(PropertyValue::String(option))
+                }
+                PropertyValue::String(option) => Err(PropertyError::InvalidOption(option)),
+                _ => Err(PropertyError::InvalidValueType),
+            },
+            PropertyInfo::Color { alpha, .. } => {
+                let mut color: PropertyColor = value.try_into()?;
+
+                if !alpha {
+                    color.a = 255;
+                }
+
+                Ok(PropertyValue::Color(color))
+            }
+            PropertyInfo::Font { .. } => match value {
+                PropertyValue::String(family) if !family.trim().is_empty() => {
+                    check_font_family(&family, crate::installed_font_families())?;
+                    Ok(PropertyValue::String(family))
+                }
+                _ => Err(PropertyError::InvalidValueType),
+            },
+            PropertyInfo::LineRanges { .. } => {
+                let ranges: Vec<LineRange> = value.try_into()?;
+                Ok(PropertyValue::LineRanges(ranges))
+            }
+            PropertyInfo::File { extensions, .. } => match value {
+                PropertyValue::String(path) => {
+                    let extension = Path::new(&path)
+                        .extension()
+                        .and_then(|ext| ext.to_str())
+                        .unwrap_or_default();
+
+                    if extensions.is_empty()
+                        || extensions
+                            .iter()
+                            .any(|allowed| allowed.eq_ignore_ascii_case(extension))
+                    {
+                        Ok(PropertyValue::String(path))
+                    } else {
+                        Err(PropertyError::InvalidFileExtension(path))
+                    }
+                }
+                _ => Err(PropertyError::InvalidValueType),
+            },
+        }
+    }
+
+    /// Returns the declared default of the property if it has one.
+    pub fn default_value(&self) -> Option<PropertyValue> {
+        match self {
+            PropertyInfo::String { default, .. }
+            | PropertyInfo::Enum { default, .. }
+            | PropertyInfo::Font { default, .. }
+            | PropertyInfo::File { default, .. } => default.clone().map(PropertyValue::from),
+            PropertyInfo::Integer { default, .. } => default.map(PropertyValue::from),
+            PropertyInfo::Float { default, .. } => default.map(PropertyValue::from),
+            PropertyInfo::Boolean { default, .. } => default.map(PropertyValue::from),
+            PropertyInfo::Color { default, .. } => default.map(PropertyValue::from),
+            PropertyInfo::LineRanges { default, .. } => default.clone().map(PropertyValue::from),
+        }
+    }
+}
+
+/// Checks that `family` is one of the installed `families` or a generic family like `monospace`.
+fn check_font_family(family: &str, families: &[crate::FontFamily]) -> Result<(), PropertyError> {
+    // NOTE: Without any fonts found, e.g. in a sandbox, every family would be rejected.
+    if families.is_empty()
+        || GENERIC_FONT_FAMILIES
+            .iter()
+            .copied()
+            .chain(families.iter().map(|installed| installed.name.as_str()))
+            .any(|name| name.eq_ignore_ascii_case(family.trim()))
+    {
+        Ok(())
+    } else {
+        Err(PropertyError::UnknownFontFamily(family.to_string()))
+    }
+}
+
+fn check_range(
+    value: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+    step: Option<f64>,
+) -> Result<(), PropertyError> {
+    if let Some(min) = min {
+        if value < min {
+            return Err(PropertyError::BelowMinimum { value, min });
+        }
+    }
+
+    if let Some(max) = max {
+        if value > max {
+            return Err(PropertyError::AboveMaximum { value, max });
+        }
+    }
+
+    if let Some(step) = step.filter(|step| *step > 0.0) {
+        let steps = (value - min.unwrap_or(0.0)) / step;
+
+        if (steps - steps.round()).abs() > 1e-6 {
+            return Err(PropertyError::InvalidStep { value, step });
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates `values` against the `properties` a generator declares.
+///
+/// Unknown keys and values that don't fit their property are reported with the name of the
+/// offending property, missing values are filled in with the declared defaults and
+/// properties that aren't active (see [active_properties]) are left out without being checked.
+pub fn validate_properties(
+    properties: &[PropertyInfo],
+    values: BTreeMap<String, PropertyValue>,
+) -> Result<BTreeMap<String, PropertyValue>, Vec<PropertyError>> {
+    // NOTE: Errors of invalid values keep the property name, so they can be dropped if the
+    // property turns out to be inactive.
+    let mut errors: Vec<(Option<String>, PropertyError)> = Vec::new();
+    let mut validated = BTreeMap::new();
+
+    for (name, value) in values {
+        let Some(property) = properties.iter().find(|property| property.name() == name) else {
+            errors.push((None, PropertyError::UnknownProperty(name)));
+            continue;
+        };
+
+        match property.validate(value.clone()) {
+            Ok(value) => {
+                validated.insert(name, value);
+            }
+            Err(err) => {
+                // NOTE: Kept as is, so conditions still see the value that was chosen.
+                validated.insert(name.clone(), value);
+                errors.push((
+                    Some(name.clone()),
+                    PropertyError::InvalidProperty {
+                        property: name,
+                        source: Box::new(err),
+                    },
+                ));
+            }
+        }
+    }
+
+    for property in properties {
+        if validated.contains_key(property.name()) {
+            continue;
+        }
+
+        if let Some(default) = property.default_value() {
+            validated.insert(property.name().to_string(), default);
+        }
+    }
+
+    let active = active_properties(properties, &validated)
+        .into_iter()
+        .map(String::from)
+        .collect::<BTreeSet<_>>();
+
+    validated.retain(|name, _| active.contains(name));
+
+    let errors: Vec<PropertyError> = errors
+        .into_iter()
+        .filter(|(name, _)| name.iter().all(|name| active.contains(name)))
+        .map(|(_, err)| err)
+        .collect();
+
+    if errors.is_empty() {
+        Ok(validated)
+    } else {
+        Err(errors)
+    }
+}
+
+impl From<&str> for PropertyValue {
+    fn from(value: &str) -> Self {
+        PropertyValue::String(value.to_string())
+    }
+}
+
+impl From<String> for PropertyValue {
+    fn from(value: String) -> Self {
+        PropertyValue::String(value)
+    }
+}
+
+impl From<i32> for PropertyValue {
+    fn from(value: i32) -> Self {
+        PropertyValue::Int(value)
+    }
+}
+
+impl From<f64> for PropertyValue {
+    fn from(value: f64) -> Self {
+        PropertyValue::Float(value)
+    }
+}
+
+impl From<f32> for PropertyValue {
+    fn from(value: f32) -> Self {
+        PropertyValue::Float(value as f64)
+    }
+}
+
+impl From<bool> for PropertyValue {
+    fn from(value: bool) -> Self {
+        PropertyValue::Bool(value)
+    }
+}
+
+impl From<PropertyColor> for PropertyValue {
+    fn from(value: PropertyColor) -> Self {
+        PropertyValue::Color(value)
+    }
+}
+
+impl From<Vec<LineRange>> for PropertyValue {
+    fn from(value: Vec<LineRange>) -> Self {
+        PropertyValue::LineRanges(value)
+    }
+}
+
+impl Display for PropertyValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PropertyValue::String(string) => write!(f, "{}", string),
+            PropertyValue::Int(int) => write!(f, "{}", int),
+            PropertyValue::Float(float) => write!(f, "{}", float),
+            PropertyValue::Bool(bool) => write!(f, "{}", bool),
+            PropertyValue::Color(color) => write!(f, "{}", color.to_hex(color.a != 255)),
+            PropertyValue::LineRanges(ranges) => write!(
+                f,
+                "{}",
+                ranges
+                    .iter()
+                    .map(LineRange::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            PropertyValue::Expression { expression } => write!(f, "{}", expression),
+        }
+    }
+}
+
+impl TryInto<f64> for PropertyValue {
+    type Error = PropertyError;
+    fn try_into(self) -> Result<f64, Self::Error> {
+        match self {
+            PropertyValue::Float(float) => Ok(float),
+            PropertyValue::Int(int) => Ok(int as f64),
+            _ => Err(PropertyError::InvalidValueType),
+        }
+    }
+}
+
+impl TryInto<i32> for PropertyValue {
+    type Error = PropertyError;
+    fn try_into(self) -> Result<i32, Self::Error> {
+        match self {
+            PropertyValue::Int(int) => Ok(int),
+            PropertyValue::Float(float) => Ok(float as i32),
+            _ => Err(PropertyError::InvalidValueType),
+        }
+    }
+}
+
+impl TryInto<bool> for PropertyValue {
+    type Error = PropertyError;
+    fn try_into(self) -> Result<bool, Self::Error> {
+        match self {
+            PropertyValue::Bool(bool) => Ok(bool),
+            PropertyValue::Int(int) => Ok(int != 0),
+            _ => Err(PropertyError::InvalidValueType),
+        }
+    }
+}
+
+impl TryInto<PropertyColor> for PropertyValue {
+    type Error = PropertyError;
+    fn try_into(self) -> Result<PropertyColor, Self::Error> {
+        match self {
+            PropertyValue::Color(color) => Ok(color),
+            PropertyValue::String(string) => PropertyColor::from_str(&string),
+            _ => Err(PropertyError::InvalidValueType),
+        }
+    }
+}
+
+impl TryInto<Vec<LineRange>> for PropertyValue {
+    type Error = PropertyError;
+    fn try_into(self) -> Result<Vec<LineRange>, Self::Error> {
+        match self {
+            PropertyValue::LineRanges(ranges) => {
+                for range in &ranges {
+                    range.validate()?;
+                }
+
+                Ok(ranges)
+            }
+            PropertyValue::String(string) => LineRange::parse_list(&string),
+            PropertyValue::Int(line) if line > 0 => {
+                Ok(vec![LineRange::new(line as u32, line as u32)])
+            }
+            _ => Err(PropertyError::InvalidValueType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_property_colors() {
+        let colors = [
+            ("#fff", PropertyColor::new(255, 255, 255, 255)),
+            ("#f008", PropertyColor::new(255, 0, 0, 136)),
+            ("#1e1e2e", PropertyColor::new(30, 30, 46, 255)),
+            ("#1e1e2e80", PropertyColor::new(30, 30, 46, 128)),
+        ];
+
+        for (string, expected) in colors {
+            assert_eq!(PropertyColor::from_str(string).unwrap(), expected);
+        }
+
+        assert!(PropertyColor::from_str("fff").is_err());
+        assert!(PropertyColor::from_str("#ggg").is_err());
+    }
+
+    #[test]
+    fn parse_line_ranges() {
+        assert_eq!(
+            LineRange::parse_list("1-4, 8,10-12").unwrap(),
+            vec![
+                LineRange::new(1, 4),
+                LineRange::new(8, 8),
+                LineRange::new(10, 12)
+            ]
+        );
+
+        assert!(LineRange::parse_list("4-1").is_err());
+        assert!(LineRange::parse_list("0").is_err());
+    }
+
+    #[test]
+    fn validate_new_property_kinds() {
+        let frame = PropertyInfo::Enum {
+            name: "frame_style".to_string(),
+            description: String::new(),
+            default: None,
+            options: vec!["none".to_string(), "macos".to_string()],
+            depends_on: Vec::new(),
+            display_name: None,
+            disables: Vec::new(),
+        };
+
+        assert!(frame.validate("macos".into()).is_ok());
+        assert!(matches!(
+            frame.validate("windows".into()),
+            Err(PropertyError::InvalidOption(_))
+        ));
+
+        let color = PropertyInfo::Color {
+            name: "shadow".to_string(),
+            description: String::new(),
+            default: None,
+            alpha: false,
+            depends_on: Vec::new(),
+            display_name: None,
+            disables: Vec::new(),
+        };
+
+        assert_eq!(
+            color.validate("#00000080".into()).unwrap(),
+            PropertyValue::Color(PropertyColor::new(0, 0, 0, 255))
+        );
+
+        let file = PropertyInfo::File {
+            name: "background_image".to_string(),
+            description: String::new(),
+            default: None,
+            extensions: vec!["png".to_string()],
+            depends_on: Vec::new(),
+            display_name: None,
+            disables: Vec::new(),
+        };
+
+        assert!(file.validate("image.PNG".into()).is_ok());
+        assert!(file.validate("image.jpg".into()).is_err());
+
+        let families = [crate::FontFamily {
+            name: "Fira Code".to_string(),
+            monospace: true,
+        }];
+
+        assert!(check_font_family("fira code", &families).is_ok());
+        assert!(check_font_family("monospace", &families).is_ok());
+        assert!(matches!(
+            check_font_family("Comic Sans", &families),
+            Err(PropertyError::UnknownFontFamily(_))
+        ));
+    }
+
+    #[test]
+    fn validate_property_map() {
+        let properties = [
+            PropertyInfo::Integer {
+                name: "padding".to_string(),
+                description: String::new(),
+                default: Some(16),
+                min: Some(0),
+                max: Some(128),
+                step: Some(4),
+                depends_on: Vec::new(),
+                display_name: None,
+                disables: Vec::new(),
+            },
+            PropertyInfo::Boolean {
+                name: "include_background".to_string(),
+                description: String::new(),
+                default: Some(true),
+                depends_on: Vec::new(),
+                display_name: None,
+                disables: Vec::new(),
+            },
+        ];
+
+        let values = validate_properties(&properties, BTreeMap::new()).unwrap();
+        assert_eq!(values.get("padding"), Some(&PropertyValue::Int(16)));
+        assert_eq!(
+            values.get("include_background"),
+            Some(&PropertyValue::Bool(true))
+        );
+
+        let errors = validate_properties(
+            &properties,
+            BTreeMap::from([
+                ("padding".to_string(), PropertyValue::Int(6)),
+                ("tab_size".to_string(), PropertyValue::Int(4)),
+            ]),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            errors,
+            vec![
+                PropertyError::InvalidProperty {
+                    property: "padding".to_string(),
+                    source: Box::new(PropertyError::InvalidStep {
+                        value: 6.0,
+                        step: 4.0
+                    }),
+                },
+                PropertyError::UnknownProperty("tab_size".to_string()),
+            ]
+        );
+
+        let properties = [
+            PropertyInfo::Boolean {
+                name: "include_background".to_string(),
+                description: String::new(),
+                default: Some(true),
+                depends_on: Vec::new(),
+                display_name: None,
+                disables: Vec::new(),
+            },
+            PropertyInfo::Color {
+                name: "background".to_string(),
+                description: String::new(),
+                default: None,
+                alpha: false,
+                depends_on: vec![PropertyCondition::from_str("include_background").unwrap()],
+                display_name: None,
+                disables: Vec::new(),
+            },
+        ];
+
+        let values = validate_properties(
+            &properties,
+            BTreeMap::from([
+                ("include_background".to_string(), PropertyValue::Bool(false)),
+                ("background".to_string(), "not a color".into()),
+            ]),
+        )
+        .unwrap();
+
+        assert!(!values.contains_key("background"));
+    }
+}
@@ -0,0 +1,285 @@
+//! Application settings stored in `config.toml` in [crate::dir::config_dir].
+//!
+//! The file is created from `templates/config.toml` on first run. Keys added to the template
+//! later are copied into existing files, along with their comments, when the settings are loaded.
+
+use std::path::Path;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tinytemplate::TinyTemplate;
+use toml_edit::{DocumentMut, Item, Table};
+use ts_rs::TS;
+
+use crate::util::set_toml_value;
+
+pub const CONFIG_FILE: &str = "config.toml";
+
+const TEMPLATE: &str = include_str!("../templates/config.toml");
+
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("Failed to access config file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to parse config file: {0}")]
+    Parse(#[from] toml_edit::TomlError),
+    #[error("Failed to render config template: {0}")]
+    Template(#[from] tinytemplate::error::Error),
+    #[error("Config key \"{0}\" has the wrong type")]
+    InvalidValue(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Settings {
+    pub code: CodeSettings,
+}
+
+/// Settings of the `[code]` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct CodeSettings {
+    /// Theme used for syntax highlighting, editor, and generators
+    pub theme: String,
+    /// Language syntax used for syntax highlighting
+    pub syntax: String,
+    pub font_family: String,
+    pub font_size: f32,
+}
+
+impl Default for CodeSettings {
+    fn default() -> Self {
+        CodeSettings {
+            theme: "base16-ocean.dark".to_string(),
+            syntax: "Plain Text".to_string(),
+            font_family: "Monospace".to_string(),
+            font_size: 12.0,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TemplateContext {
+    theme: String,
+    syntax: String,
+    font_family: String,
+    font_size: String,
+}
+
+impl Settings {
+    /// Renders the config template with these settings.
+    pub fn render(&self) -> Result<String, SettingsError> {
+        let mut template = TinyTemplate::new();
+        template.set_default_formatter(&tinytemplate::format_unescaped);
+        template.add_template("config", TEMPLATE)?;
+
+        let context = TemplateContext {
+            theme: escape(&self.code.theme),
+            syntax: escape(&self.code.syntax),
+            font_family: escape(&self.code.font_family),
+            font_size: format!("{:?}", self.code.font_size),
+        };
+
+        Ok(template.render("config", &context)?)
+    }
+
+    /// Loads the settings from `path`, creating the file from the template on first run.
+    ///
+    /// Keys missing from an existing file are added from the template and the file is
+    /// written back.
+    pub fn load_or_create(path: &Path) -> Result<Settings, SettingsError> {
+        let defaults = Settings::default();
+
+        if !path.exists() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            std::fs::write(path, defaults.render()?)?;
+            return Ok(defaults);
+        }
+
+        let mut document: DocumentMut = std::fs::read_to_string(path)?.parse()?;
+        let template: DocumentMut = defaults.render()?.parse()?;
+
+        if migrate(&mut document, &template) {
+            std::fs::write(path, document.to_string())?;
+        }
+
+        Settings::from_document(&document)
+    }
+
+    /// Writes the settings to `path`, keeping the comments and formatting of the existing file.
+    pub fn save(&self, path: &Path) -> Result<(), SettingsError> {
+        let mut document: DocumentMut = match std::fs::read_to_string(path) {
+            Ok(content) => content.parse()?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => self.render()?.parse()?,
+            Err(err) => return Err(err.into()),
+        };
+
+        if !document.get("code").is_some_and(Item::is_table) {
+            document.insert("code", Item::Table(Table::new()));
+        }
+
+        let code = document["code"].as_table_mut().expect("code is a table");
+        set_toml_value(code, "theme", self.code.theme.as_str().into());
+        set_toml_value(code, "syntax", self.code.syntax.as_str().into());
+        set_toml_value(code, "font_family", self.code.font_family.as_str().into());
+        set_toml_value(code, "font_size", (self.code.font_size as f64).into());
+
+        std::fs::write(path, document.to_string())?;
+
+        Ok(())
+    }
+
+    /// Replaces unknown names with the first theme or syntax and invalid values with the
+    /// defaults, returning a description of every correction.
+    pub fn validate(&mut self, themes: &[String], syntaxes: &[String]) -> Vec<String> {
+        let mut corrections = Vec::new();
+        let defaults = CodeSettings::default();
+
+        for (kind, name, names) in [
+            ("Theme", &mut self.code.theme, themes),
+            ("Syntax", &mut self.code.syntax, syntaxes),
+        ] {
+            if !names.contains(name) {
+                if let Some(first) = names.first() {
+                    corrections.push(format!("{kind} \"{name}\" not found, using \"{first}\""));
+                    *name = first.clone();
+                }
+            }
+        }
+
+        if self.code.font_family.trim().is_empty() {
+            corrections.push(format!(
+                "Font family is empty, using \"{}\"",
+                defaults.font_family
+            ));
+            self.code.font_family = defaults.font_family;
+        }
+
+        if !self.code.font_size.is_finite() || self.code.font_size <= 0.0 {
+            corrections.push(format!(
+                "Font size {} is invalid, using {}",
+                self.code.font_size, defaults.font_size
+            ));
+            self.code.font_size = defaults.font_size;
+        }
+
+        for correction in &corrections {
+            warn!("{correction}");
+        }
+
+        corrections
+    }
+
+    fn from_document(document: &DocumentMut) -> Result<Settings, SettingsError> {
+        let code = document
+            .get("code")
+            .and_then(Item::as_table_like)
+            .ok_or_else(|| SettingsError::InvalidValue("code".to_string()))?;
+
+        let string = |key: &str| {
+            code.get(key)
+                .and_then(Item::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| SettingsError::InvalidValue(format!("code.{key}")))
+        };
+
+        let font_size = code
+            .get("font_size")
+            .and_then(|item| {
+                item.as_float()
+                    .or_else(|| item.as_integer().map(|int| int as f64))
+            })
+            .ok_or_else(|| SettingsError::InvalidValue("code.font_size".to_string()))?;
+
+        Ok(Settings {
+            code: CodeSettings {
+                theme: string("theme")?,
+                syntax: string("syntax")?,
+                font_family: string("font_family")?,
+                font_size: font_size as f32,
+            },
+        })
+    }
+}
+
+/// Copies tables and keys that are missing from `document` from `template`, including their
+/// comments. Returns whether anything was added.
+fn migrate(document: &mut Table, template: &Table) -> bool {
+    let mut changed = false;
+
+    for (key, item) in template.iter() {
+        match document.get_mut(key) {
+            Some(Item::Table(table)) => {
+                if let Some(template) = item.as_table() {
+                    changed |= migrate(table, template);
+                }
+            }
+            Some(_) => {}
+            None => {
+                let (key, item) = template.get_key_value(key).expect("key exists");
+                document.insert_formatted(key, item.clone());
+                changed = true;
+            }
+        }
+    }
+
+    changed
+}
+
+/// Escapes a value for use inside a quoted TOML string in the template.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_and_validate_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            r#"# My settings
+[code]
+theme = "Missing" # not installed yet
+syntax = "Rust"
+"#,
+        )
+        .unwrap();
+
+        let mut settings = Settings::load_or_create(&path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+
+        assert!(content.contains("# not installed yet"));
+        assert!(content.contains("# Font settings for the editor and generators"));
+        assert_eq!(settings.code.font_family, "Monospace");
+
+        let corrections = settings.validate(
+            &["Nord".to_string(), "Monokai".to_string()],
+            &["Rust".to_string()],
+        );
+        assert_eq!(corrections.len(), 1);
+        assert_eq!(settings.code.theme, "Nord");
+        assert_eq!(settings.code.syntax, "Rust");
+
+        settings.code.font_size = 16.0;
+        settings.save(&path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+
+        assert!(content.contains("theme = \"Nord\" # not installed yet"));
+        assert!(content.contains("font_size = 16.0"));
+    }
+}
@@ -0,0 +1,255 @@
+//! Opening source files and batch processing directories.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use syntect::parsing::SyntaxSet;
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::{
+    generator::{GeneratorInfo, JobId},
+    property::LineRange,
+    syntax::{detect_syntax, DetectedSyntax},
+};
+
+/// Extension of outputs of generators that don't declare any extensions.
+const FALLBACK_EXTENSION: &str = "txt";
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("Failed to read {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Line range {range} is outside of the {lines} lines of the file")]
+    InvalidRange { range: LineRange, lines: usize },
+    #[error("Invalid glob pattern: {0}")]
+    InvalidPattern(#[from] glob::PatternError),
+}
+
+/// A source file opened for snapshotting.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    /// The selected lines of the file, or the whole file
+    pub code: String,
+    /// Number of lines in the whole file
+    pub lines: usize,
+    #[ts(optional)]
+    pub syntax: Option<DetectedSyntax>,
+}
+
+/// Result of generating the snapshot of a single file of a batch.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct BatchOutput {
+    pub input: PathBuf,
+    /// The written files, two for separate light and dark variants
+    pub outputs: Vec<PathBuf>,
+    /// Whether the batch was cancelled while generating the file, nothing is written then
+    pub cancelled: bool,
+    #[ts(optional)]
+    pub error: Option<String>,
+}
+
+/// Payload of the `batch-progress` event, sent before each file is processed.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct BatchProgress {
+    /// The generator job of the batch, which can be cancelled
+    pub job_id: JobId,
+    pub current: usize,
+    pub total: usize,
+    pub input: PathBuf,
+}
+
+/// Reads a source file, detecting its syntax from the path and the whole content.
+pub fn open_source_file(
+    syntax_set: &SyntaxSet,
+    path: &Path,
+    range: Option<LineRange>,
+) -> Result<SourceFile, SnapshotError> {
+    let text = std::fs::read_to_string(path).map_err(|source| SnapshotError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let code = match range {
+        Some(range) => select_lines(&text, range)?,
+        None => text.clone(),
+    };
+
+    Ok(SourceFile {
+        path: path.to_path_buf(),
+        code,
+        lines: text.lines().count(),
+        syntax: detect_syntax(syntax_set, Some(path), &text),
+    })
+}
+
+/// Returns the lines of `text` in `range`, keeping their line endings.
+pub fn select_lines(text: &str, range: LineRange) -> Result<String, SnapshotError> {
+    let lines = text.lines().count();
+
+    if range.start == 0 || range.start > range.end || range.start as usize > lines {
+        return Err(SnapshotError::InvalidRange { range, lines });
+    }
+
+    let selected: String = text
+        .split_inclusive('\n')
+        .skip(range.start as usize - 1)
+        .take((range.end - range.start + 1) as usize)
+        .collect();
+
+    Ok(selected)
+}
+
+/// Files matching a glob pattern such as `src/**/*.rs`, sorted by path.
+pub fn batch_inputs(pattern: &str) -> Result<Vec<PathBuf>, SnapshotError> {
+    let mut inputs: Vec<PathBuf> = glob::glob(pattern)?
+        .filter_map(|entry| entry.ok())
+        .filter(|path| path.is_file())
+        .collect();
+
+    inputs.sort();
+    Ok(inputs)
+}
+
+/// The directory a glob pattern such as `src/**/*.rs` matches files in, i.e. its components
+/// before the first one with a wildcard.
+pub fn pattern_base(pattern: &str) -> PathBuf {
+    Path::new(pattern)
+        .components()
+        .take_while(|component| {
+            !component
+                .as_os_str()
+                .to_string_lossy()
+                .contains(['*', '?', '['])
+        })
+        .collect()
+}
+
+/// Path of the output for `input` in `output_dir`.
+///
+/// The path of the input relative to `base_dir` is kept, so `a/main.rs` and `b/main.rs` don't
+/// overwrite each other, and neither do `main.rs` and `main.py`, e.g. `src/main.rs` becomes
+/// `src/main.rs.svg` for a generator with the `svg` extension. Inputs outside of `base_dir` only
+/// keep their file name.
+pub fn output_path(
+    info: &GeneratorInfo,
+    input: &Path,
+    base_dir: &Path,
+    output_dir: &Path,
+) -> PathBuf {
+    let extension = info
+        .extensions()
+        .and_then(|extensions| extensions.first().copied())
+        .unwrap_or(FALLBACK_EXTENSION);
+
+    let relative = input
+        .strip_prefix(base_dir)
+        .ok()
+        .filter(|relative| relative.file_name().is_some())
+        .or_else(|| input.file_name().map(Path::new))
+        .unwrap_or(input);
+
+    let mut file_name = relative.as_os_str().to_owned();
+    file_name.push(format!(".{extension}"));
+
+    output_dir.join(file_name)
+}
+
+/// Path of the output of a theme `variant` such as `light`, next to the output at `path`, e.g.
+/// `main.rs.svg` becomes `main.rs.light.svg`.
+pub fn variant_path(path: &Path, variant: &str) -> PathBuf {
+    let mut file_name = path.file_stem().unwrap_or_default().to_owned();
+    file_name.push(format!(".{variant}"));
+
+    if let Some(extension) = path.extension() {
+        file_name.push(".");
+        file_name.push(extension);
+    }
+
+    path.with_file_name(file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{GeneratorExt, SvgGenerator};
+
+    #[test]
+    fn select_line_ranges() {
+        let text = "one\ntwo\nthree\nfour";
+
+        assert_eq!(
+            select_lines(text, LineRange::new(2, 3)).unwrap(),
+            "two\nthree\n"
+        );
+        assert_eq!(select_lines(text, LineRange::new(4, 10)).unwrap(), "four");
+        assert!(select_lines(text, LineRange::new(5, 6)).is_err());
+        assert!(select_lines(text, LineRange::new(3, 2)).is_err());
+    }
+
+    #[test]
+    fn derive_output_paths() {
+        let info = SvgGenerator::information();
+
+        assert_eq!(
+            output_path(
+                &info,
+                Path::new("src/main.rs"),
+                Path::new(""),
+                Path::new("out")
+            ),
+            PathBuf::from("out/src/main.rs.svg")
+        );
+        assert_eq!(
+            output_path(
+                &info,
+                Path::new("main.rs"),
+                Path::new("main.rs"),
+                Path::new("out")
+            ),
+            PathBuf::from("out/main.rs.svg")
+        );
+        assert_eq!(pattern_base("src/**/*.rs"), PathBuf::from("src"));
+        assert_eq!(pattern_base("*.rs"), PathBuf::new());
+        assert_eq!(
+            variant_path(Path::new("out/main.rs.svg"), "dark"),
+            PathBuf::from("out/main.rs.dark.svg")
+        );
+    }
+
+    #[test]
+    fn keep_same_named_batch_outputs_apart() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        for sub_dir in ["a", "b"] {
+            std::fs::create_dir_all(dir.join(sub_dir)).unwrap();
+            std::fs::write(dir.join(sub_dir).join("main.rs"), "fn main() {}").unwrap();
+        }
+
+        let pattern = format!("{}/**/main.rs", dir.display());
+        let info = SvgGenerator::information();
+        let outputs: Vec<PathBuf> = batch_inputs(&pattern)
+            .unwrap()
+            .iter()
+            .map(|input| output_path(&info, input, &pattern_base(&pattern), Path::new("out")))
+            .collect();
+
+        assert_eq!(
+            outputs,
+            [
+                PathBuf::from("out/a/main.rs.svg"),
+                PathBuf::from("out/b/main.rs.svg")
+            ]
+        );
+    }
+}
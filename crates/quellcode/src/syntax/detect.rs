@@ -0,0 +1,366 @@
+use std::path::Path;
+
+use serde::Serialize;
+use syntect::{
+    easy::ScopeRegionIterator,
+    parsing::{ParseState, Scope, ScopeStack, SyntaxReference, SyntaxSet},
+};
+use ts_rs::TS;
+
+/// Number of lines searched for modelines at the start and end of the text.
+const MODELINE_LINES: usize = 5;
+/// Number of lines the heuristic parses with every candidate syntax.
+const SAMPLE_LINES: usize = 40;
+/// Minimum share of the sample recognized as tokens for the heuristic to accept a syntax.
+const MIN_SCORE: f64 = 0.4;
+/// Scopes of the tokens a syntax recognizes, `meta` scopes and the base scope only tell where a
+/// token is and are given to any text.
+const TOKEN_SCOPES: &[&str] = &[
+    "keyword",
+    "storage",
+    "entity",
+    "support",
+    "constant",
+    "string",
+    "punctuation",
+];
+/// Token scopes given to any word, e.g. YAML's plain scalars or Erlang's atoms.
+const CATCH_ALL_SCOPES: &[&str] = &["string.unquoted", "constant.other.symbol"];
+
+/// Interpreters whose name doesn't match a syntax name or extension.
+const INTERPRETERS: &[(&str, &str)] = &[
+    ("bash", "sh"),
+    ("zsh", "sh"),
+    ("dash", "sh"),
+    ("ksh", "sh"),
+    ("node", "js"),
+    ("nodejs", "js"),
+    ("deno", "ts"),
+    ("bun", "ts"),
+    ("Rscript", "r"),
+    ("runghc", "hs"),
+    ("runhaskell", "hs"),
+    ("escript", "erl"),
+    ("osascript", "applescript"),
+    ("pwsh", "ps1"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum DetectionMethod {
+    /// A vim or emacs modeline, e.g. `# vim: set ft=python:`
+    Modeline,
+    /// The file name or extension
+    FileName,
+    /// The interpreter of a `#!` line
+    Shebang,
+    /// The `first_line_match` of a syntax
+    FirstLine,
+    /// The syntax that highlights most of a sample of the text
+    Heuristic,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct DetectedSyntax {
+    pub name: String,
+    pub method: DetectionMethod,
+}
+
+/// Detects the syntax of `text`, optionally read from `path`.
+///
+/// Explicit hints are tried first: modelines, then the file name, the shebang and the first
+/// line. If none of them match, every syntax is scored on a sample of the text.
+pub fn detect_syntax(
+    syntax_set: &SyntaxSet,
+    path: Option<&Path>,
+    text: &str,
+) -> Option<DetectedSyntax> {
+    let detected = |syntax: &SyntaxReference, method| DetectedSyntax {
+        name: syntax.name.clone(),
+        method,
+    };
+
+    if let Some(syntax) = find_by_modeline(syntax_set, text) {
+        return Some(detected(syntax, DetectionMethod::Modeline));
+    }
+
+    if let Some(syntax) = path.and_then(|path| find_by_path(syntax_set, path)) {
+        return Some(detected(syntax, DetectionMethod::FileName));
+    }
+
+    let first_line = text.lines().next().unwrap_or_default();
+
+    if let Some(syntax) = find_by_shebang(syntax_set, first_line) {
+        return Some(detected(syntax, DetectionMethod::Shebang));
+    }
+
+    if let Some(syntax) = syntax_set.find_syntax_by_first_line(first_line) {
+        return Some(detected(syntax, DetectionMethod::FirstLine));
+    }
+
+    find_by_heuristic(syntax_set, text).map(|syntax| detected(syntax, DetectionMethod::Heuristic))
+}
+
+fn find_by_path<'a>(syntax_set: &'a SyntaxSet, path: &Path) -> Option<&'a SyntaxReference> {
+    // NOTE: Syntaxes list file names such as `Makefile` as extensions, so try the whole name
+    // first.
+    let file_name = path.file_name()?.to_str()?;
+
+    syntax_set.find_syntax_by_extension(file_name).or_else(|| {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| syntax_set.find_syntax_by_extension(ext))
+    })
+}
+
+fn find_by_shebang<'a>(syntax_set: &'a SyntaxSet, first_line: &str) -> Option<&'a SyntaxReference> {
+    let mut words = first_line.strip_prefix("#!")?.split_whitespace();
+    let mut interpreter = words.next()?.rsplit('/').next()?;
+
+    if interpreter == "env" {
+        // NOTE: Skip options such as `env -S deno run`.
+        interpreter = words.find(|word| !word.starts_with('-') && !word.contains('='))?;
+    }
+
+    // NOTE: Strip versions such as `python3` or `python3.12`.
+    let interpreter = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+
+    let token = INTERPRETERS
+        .iter()
+        .find(|(name, _)| *name == interpreter)
+        .map_or(interpreter, |(_, token)| *token);
+
+    syntax_set.find_syntax_by_token(token)
+}
+
+fn find_by_modeline<'a>(syntax_set: &'a SyntaxSet, text: &str) -> Option<&'a SyntaxReference> {
+    let lines: Vec<&str> = text.lines().collect();
+    let head = lines.iter().take(MODELINE_LINES);
+    let tail = lines.iter().skip(MODELINE_LINES).rev().take(MODELINE_LINES);
+
+    head.chain(tail)
+        .find_map(|line| parse_modeline(line))
+        .and_then(|token| syntax_set.find_syntax_by_token(token))
+}
+
+/// Extracts the language of a vim (`vim: set ft=rust:`) or emacs (`-*- mode: rust -*-`)
+/// modeline.
+fn parse_modeline(line: &str) -> Option<&str> {
+    if let Some(start) = line.find("-*-") {
+        let rest = &line[start + 3..];
+        let content = &rest[..rest.find("-*-")?];
+
+        let mode = if content.contains(':') {
+            content.split(';').find_map(|variable| {
+                let (key, value) = variable.split_once(':')?;
+                key.trim()
+                    .eq_ignore_ascii_case("mode")
+                    .then(|| value.trim())
+            })
+        } else {
+            Some(content.trim())
+        };
+
+        return mode.filter(|mode| !mode.is_empty());
+    }
+
+    // NOTE: Markers must start a word, `index: 1` is not an `ex:` modeline.
+    let start = ["vim:", "vi:", "ex:"]
+        .iter()
+        .filter_map(|marker| {
+            line.match_indices(marker)
+                .find(|(index, _)| {
+                    line[..*index]
+                        .chars()
+                        .next_back()
+                        .is_none_or(char::is_whitespace)
+                })
+                .map(|(index, _)| index + marker.len())
+        })
+        .min()?;
+
+    line[start..]
+        .split(|c: char| c.is_whitespace() || c == ':')
+        .find_map(|option| {
+            option
+                .strip_prefix("ft=")
+                .or_else(|| option.strip_prefix("filetype="))
+                .or_else(|| option.strip_prefix("syntax="))
+                .or_else(|| option.strip_prefix("syn="))
+        })
+        .filter(|filetype| !filetype.is_empty())
+}
+
+fn find_by_heuristic<'a>(syntax_set: &'a SyntaxSet, text: &str) -> Option<&'a SyntaxReference> {
+    let sample: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .take(SAMPLE_LINES)
+        .collect();
+
+    if sample.is_empty() {
+        return None;
+    }
+
+    // NOTE: Only source code is detected, text formats such as Markdown, CSV or plain text
+    // accept any text and would match prose as well as code.
+    let source = Scope::new("source").expect("valid scope");
+
+    syntax_set
+        .syntaxes()
+        .iter()
+        .filter(|syntax| !syntax.hidden && source.is_prefix_of(syntax.scope))
+        .map(|syntax| (syntax, score(syntax_set, syntax, &sample)))
+        .filter(|(_, score)| *score >= MIN_SCORE)
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(syntax, _)| syntax)
+}
+
+/// Share of the sample scoped as tokens, with tokens scoped as `invalid` counting against the
+/// syntax.
+///
+/// The share is measured both by non-whitespace characters and by tokens and the lower one is
+/// returned, so neither a runaway string swallowing the sample nor many tiny tokens between
+/// unrecognized words make a syntax look like a match.
+fn score(syntax_set: &SyntaxSet, syntax: &SyntaxReference, sample: &[&str]) -> f64 {
+    let scopes = |names: &[&str]| -> Vec<Scope> {
+        names
+            .iter()
+            .map(|name| Scope::new(name).expect("valid scope"))
+            .collect()
+    };
+    let invalid = Scope::new("invalid").expect("valid scope");
+    let tokens = scopes(TOKEN_SCOPES);
+    let catch_all = scopes(CATCH_ALL_SCOPES);
+
+    let mut state = ParseState::new(syntax);
+    let mut stack = ScopeStack::new();
+    // NOTE: Counted as (total, scoped, illegal).
+    let mut characters = (0usize, 0usize, 0usize);
+    let mut regions = (0usize, 0usize, 0usize);
+
+    for line in sample {
+        let Ok(ops) = state.parse_line(line, syntax_set) else {
+            return 0.0;
+        };
+
+        for (region, op) in ScopeRegionIterator::new(&ops, line) {
+            if stack.apply(op).is_err() {
+                return 0.0;
+            }
+
+            let count = region.chars().filter(|c| !c.is_whitespace()).count();
+            if count == 0 {
+                continue;
+            }
+
+            characters.0 += count;
+            regions.0 += 1;
+
+            let scopes = stack.as_slice();
+            if scopes.iter().any(|scope| invalid.is_prefix_of(*scope)) {
+                characters.2 += count;
+                regions.2 += 1;
+            } else if scopes.iter().any(|scope| {
+                tokens.iter().any(|token| token.is_prefix_of(*scope))
+                    && !catch_all.iter().any(|other| other.is_prefix_of(*scope))
+            }) {
+                characters.1 += count;
+                regions.1 += 1;
+            }
+        }
+    }
+
+    if characters.0 == 0 {
+        return 0.0;
+    }
+
+    let share = |(total, scoped, illegal): (usize, usize, usize)| {
+        (scoped as f64 - 3.0 * illegal as f64) / total as f64
+    };
+
+    share(characters).min(share(regions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(path: Option<&str>, text: &str) -> Option<(String, DetectionMethod)> {
+        let syntax_set = SyntaxSet::load_defaults_nonewlines();
+
+        detect_syntax(&syntax_set, path.map(Path::new), text)
+            .map(|detected| (detected.name, detected.method))
+    }
+
+    #[test]
+    fn detect_syntax_from_hints() {
+        assert_eq!(
+            detect(Some("src/main.rs"), "fn main() {}"),
+            Some(("Rust".to_string(), DetectionMethod::FileName))
+        );
+        assert_eq!(
+            detect(Some("Makefile"), "all:\n\tcc main.c"),
+            Some(("Makefile".to_string(), DetectionMethod::FileName))
+        );
+        assert_eq!(
+            detect(Some("script"), "#!/usr/bin/env python3\nprint('hi')"),
+            Some(("Python".to_string(), DetectionMethod::Shebang))
+        );
+        assert_eq!(
+            detect(None, "#!/bin/zsh\necho hi"),
+            Some((
+                "Bourne Again Shell (bash)".to_string(),
+                DetectionMethod::Shebang
+            ))
+        );
+        assert_eq!(
+            detect(Some("notes.txt"), "// vim: set ft=rust:\nfn main() {}"),
+            Some(("Rust".to_string(), DetectionMethod::Modeline))
+        );
+        assert_eq!(
+            detect(None, "; -*- mode: lisp -*-\n(defun f ())"),
+            Some(("Lisp".to_string(), DetectionMethod::Modeline))
+        );
+        assert_eq!(
+            detect(None, "<?xml version=\"1.0\"?>\n<root/>"),
+            Some(("XML".to_string(), DetectionMethod::FirstLine))
+        );
+    }
+
+    #[test]
+    fn detect_syntax_by_heuristic() {
+        assert_eq!(
+            detect(
+                None,
+                "fn main() {\n    let answer: u32 = 42;\n    println!(\"{answer}\");\n}"
+            ),
+            Some(("Rust".to_string(), DetectionMethod::Heuristic))
+        );
+        assert_eq!(
+            detect(
+                Some("notes"),
+                "The quick brown fox jumps over the lazy dog.\n\nIt was a bright cold day in \
+                 April, and the clocks were striking thirteen."
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_modelines() {
+        assert_eq!(parse_modeline("# vim: set ft=python ts=4:"), Some("python"));
+        assert_eq!(parse_modeline("/* vi: syntax=c */"), Some("c"));
+        assert_eq!(parse_modeline("-*- ruby -*-"), Some("ruby"));
+        assert_eq!(
+            parse_modeline("# -*- coding: utf-8; mode: perl -*-"),
+            Some("perl")
+        );
+        assert_eq!(parse_modeline("# -*- coding: utf-8 -*-"), None);
+        assert_eq!(parse_modeline("let vim = 1;"), None);
+        assert_eq!(parse_modeline("index: 1"), None);
+    }
+}
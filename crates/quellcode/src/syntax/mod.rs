@@ -0,0 +1,302 @@
+//! Loading of the bundled syntaxes together with the syntaxes in [crate::dir::code_syntax_dir].
+//!
+//! Each file is loaded on its own so a broken syntax doesn't take down the others, and syntaxes
+//! that include a syntax that isn't available are left out instead of failing while highlighting.
+//! The resulting set is cached as a dump in [crate::dir::cache_dir] until the files change.
+
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeSet},
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use log::{debug, error, info, warn};
+use syntect::parsing::{
+    syntax_definition::{ContextReference, MatchOperation, Pattern},
+    SyntaxDefinition, SyntaxSet,
+};
+
+use crate::{bundled, watcher::FileError};
+
+mod detect;
+
+pub use detect::*;
+
+const SYNTAX_EXTENSION: &str = "sublime-syntax";
+const CACHE_PREFIX: &str = "syntaxes-";
+const CACHE_EXTENSION: &str = "packdump";
+
+/// Loads the [bundled](crate::bundled) syntaxes and the syntaxes in `syntax_dir`.
+///
+/// Returns the syntax set and the files that failed to load. If `cache_dir` is given, a dump of
+/// the set is reused as long as the syntax files are unchanged.
+pub fn load_syntaxes(syntax_dir: &Path, cache_dir: Option<&Path>) -> (SyntaxSet, Vec<FileError>) {
+    let files = syntax_files(syntax_dir);
+    let cache_file = cache_dir.map(|dir| {
+        dir.join(format!(
+            "{CACHE_PREFIX}{:016x}.{CACHE_EXTENSION}",
+            fingerprint(&files)
+        ))
+    });
+
+    if let Some(cache_file) = cache_file.as_ref().filter(|file| file.exists()) {
+        match syntect::dumps::from_uncompressed_dump_file::<SyntaxSet>(cache_file) {
+            Ok(syntax_set) => {
+                debug!("Loaded syntaxes from {}", cache_file.display());
+                return (syntax_set, Vec::new());
+            }
+            Err(err) => warn!(
+                "Failed to load syntax cache {}: {err}",
+                cache_file.display()
+            ),
+        }
+    }
+
+    let defaults = bundled::syntaxes();
+    let mut errors = Vec::new();
+    let mut definitions = Vec::new();
+
+    for path in &files {
+        if path.extension().is_some_and(|ext| ext == "tmLanguage") {
+            errors.push(FileError::new(
+                path,
+                "tmLanguage syntaxes are not supported, convert them to sublime-syntax",
+            ));
+            continue;
+        }
+
+        match load_definition(path) {
+            Ok(definition) => definitions.push((path, definition)),
+            Err(err) => errors.push(FileError::new(path, err)),
+        }
+    }
+
+    // NOTE: Leaving out a syntax can break the syntaxes including it, repeat until nothing
+    // else is removed.
+    loop {
+        let available: BTreeSet<String> = defaults
+            .syntaxes()
+            .iter()
+            .flat_map(|syntax| [syntax.name.clone(), syntax.scope.to_string()])
+            .chain(definitions.iter().flat_map(|(_, definition)| {
+                [definition.name.clone(), definition.scope.to_string()]
+            }))
+            .collect();
+
+        let before = definitions.len();
+
+        definitions.retain(|(path, definition)| {
+            let missing: Vec<String> = dependencies(definition)
+                .into_iter()
+                .filter(|dependency| !available.contains(dependency))
+                .collect();
+
+            if !missing.is_empty() {
+                errors.push(FileError::new(
+                    path,
+                    format!("Depends on missing syntaxes: {}", missing.join(", ")),
+                ));
+            }
+
+            missing.is_empty()
+        });
+
+        if definitions.len() == before {
+            break;
+        }
+    }
+
+    let mut builder = defaults.into_builder();
+
+    for (_, definition) in definitions {
+        builder.add(definition);
+    }
+
+    let syntax_set = builder.build();
+
+    for error in &errors {
+        error!(
+            "Failed to load syntax {}: {}",
+            error.path.display(),
+            error.message
+        );
+    }
+
+    // NOTE: Errors aren't part of the dump, only cache clean loads so they are reported again.
+    if let (Some(cache_file), true) = (cache_file, errors.is_empty()) {
+        write_cache(&syntax_set, &cache_file);
+    }
+
+    info!("Loaded {} syntaxes", syntax_set.syntaxes().len());
+
+    (syntax_set, errors)
+}
+
+/// Names of the syntaxes defined in `paths`, files that fail to load are left out.
+pub fn syntax_names_in<'a>(paths: impl IntoIterator<Item = &'a PathBuf>) -> BTreeSet<String> {
+    paths
+        .into_iter()
+        .filter(|path| path.extension().is_some_and(|ext| ext == SYNTAX_EXTENSION))
+        .filter_map(|path| load_definition(path).ok())
+        .map(|definition| definition.name)
+        .collect()
+}
+
+/// Loads a syntax file, syntaxes without a name are named after the file.
+fn load_definition(path: &Path) -> Result<SyntaxDefinition, String> {
+    let name = path.file_stem().map(|stem| stem.to_string_lossy());
+    let content = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+
+    SyntaxDefinition::load_from_str(&content, false, name.as_deref()).map_err(|err| err.to_string())
+}
+
+/// Names and scopes of the syntaxes a definition includes.
+fn dependencies(definition: &SyntaxDefinition) -> BTreeSet<String> {
+    let mut references = Vec::new();
+
+    for context in definition.contexts.values() {
+        for pattern in &context.patterns {
+            match pattern {
+                Pattern::Include(reference) => references.push(reference),
+                Pattern::Match(pattern) => {
+                    if let MatchOperation::Push(pushed) | MatchOperation::Set(pushed) =
+                        &pattern.operation
+                    {
+                        references.extend(pushed);
+                    }
+
+                    references.extend(&pattern.with_prototype);
+                }
+            }
+        }
+    }
+
+    references
+        .into_iter()
+        .filter_map(|reference| match reference {
+            ContextReference::ByScope { scope, .. } => Some(scope.to_string()),
+            ContextReference::File { name, .. } => Some(name.clone()),
+            _ => None,
+        })
+        .filter(|dependency| {
+            *dependency != definition.name && *dependency != definition.scope.to_string()
+        })
+        .collect()
+}
+
+fn syntax_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = dir.read_dir() else {
+            continue;
+        };
+
+        for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path
+                .extension()
+                .is_some_and(|ext| ext == SYNTAX_EXTENSION || ext == "tmLanguage")
+            {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    files
+}
+
+/// Hash of the bundled syntaxes and the path, size and modification time of every syntax file.
+fn fingerprint(files: &[PathBuf]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bundled::SYNTAXES_HASH.hash(&mut hasher);
+
+    for path in files {
+        path.hash(&mut hasher);
+
+        if let Ok(metadata) = path.metadata() {
+            metadata.len().hash(&mut hasher);
+            metadata.modified().ok().hash(&mut hasher);
+        }
+    }
+
+    hasher.finish()
+}
+
+fn write_cache(syntax_set: &SyntaxSet, cache_file: &Path) {
+    let Some(cache_dir) = cache_file.parent() else {
+        return;
+    };
+
+    if let Err(err) = std::fs::create_dir_all(cache_dir) {
+        warn!("Failed to create cache dir {}: {err}", cache_dir.display());
+        return;
+    }
+
+    // NOTE: Remove dumps of previous versions of the syntax files.
+    if let Ok(entries) = cache_dir.read_dir() {
+        for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+            let is_dump = path.extension().is_some_and(|ext| ext == CACHE_EXTENSION)
+                && path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with(CACHE_PREFIX));
+
+            if is_dump {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+
+    match syntect::dumps::dump_to_uncompressed_file(syntax_set, cache_file) {
+        Ok(()) => debug!("Cached syntaxes in {}", cache_file.display()),
+        Err(err) => warn!("Failed to cache syntaxes: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_user_syntaxes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+
+        let files = [
+            (
+                "Quux.sublime-syntax",
+                "name: Quux\nscope: source.quux\ncontexts:\n  main:\n    - match: quux\n      scope: keyword.quux\n",
+            ),
+            (
+                "nested/Quuz.sublime-syntax",
+                "name: Quuz\nscope: source.quuz\ncontexts:\n  main:\n    - include: scope:source.quux\n",
+            ),
+            (
+                "Broken.sublime-syntax",
+                "name: Broken\nscope: source.broken\ncontexts:\n  main:\n    - include: scope:source.missing\n",
+            ),
+            ("Invalid.sublime-syntax", "name: [\n"),
+        ];
+
+        for (name, content) in files {
+            std::fs::write(dir.join(name), content).unwrap();
+        }
+
+        let (syntax_set, errors) = load_syntaxes(dir, None);
+        let modified = syntax_names_in(&[
+            dir.join("nested/Quuz.sublime-syntax"),
+            dir.join("Invalid.sublime-syntax"),
+        ]);
+
+        assert!(syntax_set.find_syntax_by_name("Quux").is_some());
+        assert!(syntax_set.find_syntax_by_name("Quuz").is_some());
+        assert!(syntax_set.find_syntax_by_name("Broken").is_none());
+        assert!(syntax_set.find_syntax_by_name("Rust").is_some());
+        assert_eq!(errors.len(), 2);
+        assert_eq!(modified, BTreeSet::from(["Quuz".to_string()]));
+    }
+}
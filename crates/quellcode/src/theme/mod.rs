@@ -0,0 +1,700 @@
+//! Loading theme files into a [ThemeRegistry] and reporting how each of them loaded.
+//!
+//! Themes are identified by an ID derived from where they come from, so themes with the same
+//! name in different files don't replace each other.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use log::{error, warn};
+use serde::Serialize;
+use sublime_color_scheme::{error::ParseError, ColorScheme};
+use syntect::highlighting::{Color, Theme as SnytectTheme, ThemeSet};
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::{bundled, ThemeFormat};
+
+mod overrides;
+mod pairing;
+pub use overrides::*;
+pub use pairing::*;
+
+#[derive(Debug, Error)]
+pub enum ThemeError {
+    #[error("Failed to load Sublime color scheme")]
+    Sublime(#[from] sublime_color_scheme::error::ParseError),
+    #[error("Failed to load VS Code theme")]
+    VsCode(#[from] syntect_vscode::error::ParseError),
+    #[error("Failed to load tmTheme")]
+    TmTheme(#[from] syntect::LoadingError),
+}
+
+impl ThemeError {
+    /// Messages of this error and every error that caused it, outermost first.
+    pub fn chain(&self) -> Vec<String> {
+        std::iter::successors(Some(self as &dyn std::error::Error), |err| err.source())
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    /// Where in the theme file the error occurred, if it is known.
+    pub fn location(&self) -> Option<SourceLocation> {
+        let location = match self {
+            ThemeError::Sublime(err) => err.location(),
+            ThemeError::VsCode(err) => err.location(),
+            ThemeError::TmTheme(_) => None,
+        };
+
+        location.map(|location| SourceLocation {
+            line: location.line,
+            column: location.column,
+        })
+    }
+
+    /// Renders the error as a diagnostic quoting the line of `path` where it occurred, if the
+    /// format supports it, and as its chain of causes otherwise.
+    pub fn render(&self, path: &Path) -> String {
+        // NOTE: Errors in overrides are located in the override, not in the theme at `path`.
+        if let ThemeError::Sublime(ParseError::Override {
+            path: override_path,
+            source,
+        }) = self
+        {
+            if let Ok(text) = std::fs::read_to_string(override_path) {
+                let file_name = override_path.display().to_string();
+                return format!("  × {self}\n{}", source.render(&text, &file_name));
+            }
+        }
+
+        match (self, std::fs::read_to_string(path)) {
+            (ThemeError::Sublime(err), Ok(source)) => {
+                err.render(&source, &path.display().to_string())
+            }
+            _ => format!("  × {}\n", self.chain().join("\n  ╰─▶ ")),
+        }
+    }
+}
+
+/// A theme parsed from a file.
+pub struct LoadedTheme {
+    pub name: String,
+    pub theme: SnytectTheme,
+    /// Values of the file that were ignored, e.g. colors that failed to parse
+    pub warnings: Vec<String>,
+}
+
+impl LoadedTheme {
+    pub fn info(&self, path: &Path, format: &ThemeFormat, origin: ThemeOrigin) -> ThemeInfo {
+        ThemeInfo {
+            id: ThemeInfo::file_id(origin, path),
+            name: self.name.clone(),
+            author: self.theme.author.clone(),
+            format: Some(format.clone()),
+            origin,
+            path: Some(path.to_path_buf()),
+            appearance: ThemeAppearance::of(&self.theme),
+            hidden: ThemeFormat::is_hidden(path),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SourceLocation {
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column number
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase", tag = "status")]
+#[ts(export)]
+pub enum ThemeFileStatus {
+    Loaded {
+        name: String,
+        warnings: Vec<String>,
+    },
+    Failed {
+        /// The error and its causes, outermost first
+        errors: Vec<String>,
+        #[ts(optional)]
+        location: Option<SourceLocation>,
+    },
+    /// An override that is loaded as part of the installed theme it overrides
+    Merged {
+        into: PathBuf,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ThemeFileReport {
+    pub path: PathBuf,
+    pub format: ThemeFormat,
+    pub status: ThemeFileStatus,
+}
+
+impl ThemeFileReport {
+    pub fn new(
+        path: &Path,
+        format: &ThemeFormat,
+        result: &Result<LoadedTheme, ThemeError>,
+    ) -> ThemeFileReport {
+        let status = match result {
+            Ok(loaded) => ThemeFileStatus::Loaded {
+                name: loaded.name.clone(),
+                warnings: loaded.warnings.clone(),
+            },
+            Err(err) => ThemeFileStatus::Failed {
+                errors: err.chain(),
+                location: err.location(),
+            },
+        };
+
+        ThemeFileReport {
+            path: path.to_path_buf(),
+            format: format.clone(),
+            status,
+        }
+    }
+
+    /// Report of an override that is merged into the theme at `base`.
+    pub fn merged(path: &Path, format: &ThemeFormat, base: &Path) -> ThemeFileReport {
+        ThemeFileReport {
+            path: path.to_path_buf(),
+            format: format.clone(),
+            status: ThemeFileStatus::Merged {
+                into: base.to_path_buf(),
+            },
+        }
+    }
+}
+
+/// Outcome of loading every theme file, sorted by path.
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ThemeLoadReport {
+    pub files: Vec<ThemeFileReport>,
+}
+
+impl ThemeLoadReport {
+    /// Adds the report of a file, replacing any previous report of the same file.
+    pub fn insert(&mut self, report: ThemeFileReport) {
+        match self
+            .files
+            .binary_search_by(|file| file.path.cmp(&report.path))
+        {
+            Ok(index) => self.files[index] = report,
+            Err(index) => self.files.insert(index, report),
+        }
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        self.files.retain(|file| file.path != path);
+    }
+}
+
+/// Relative luminance above which a background counts as light, the point where black and white
+/// text have the same contrast against it.
+const LIGHT_LUMINANCE: f32 = 0.179;
+
+/// Where a theme comes from, later origins take precedence when themes are looked up by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum ThemeOrigin {
+    /// Embedded in the app, including syntect's default themes
+    Bundled,
+    /// Installed from the asset store
+    Store,
+    /// Added to the theme directory by the user
+    User,
+}
+
+impl Display for ThemeOrigin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ThemeOrigin::Bundled => "bundled",
+            ThemeOrigin::Store => "store",
+            ThemeOrigin::User => "user",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum ThemeAppearance {
+    Light,
+    Dark,
+}
+
+impl ThemeAppearance {
+    /// Classifies a theme by the luminance of its background, themes without a background are
+    /// assumed to be dark.
+    pub fn of(theme: &SnytectTheme) -> ThemeAppearance {
+        match theme.settings.background {
+            Some(background) if luminance(background) > LIGHT_LUMINANCE => ThemeAppearance::Light,
+            _ => ThemeAppearance::Dark,
+        }
+    }
+}
+
+/// Relative luminance of a color as defined by WCAG, ignoring its alpha.
+pub fn luminance(color: Color) -> f32 {
+    let linear = |channel: u8| {
+        let channel = channel as f32 / 255.0;
+
+        if channel <= 0.04045 {
+            channel / 12.92
+        } else {
+            ((channel + 0.055) / 1.055).powf(2.4)
+        }
+    };
+
+    0.2126 * linear(color.r) + 0.7152 * linear(color.g) + 0.0722 * linear(color.b)
+}
+
+/// Metadata of a theme in the [ThemeRegistry].
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ThemeInfo {
+    /// Unique ID, e.g. `user:Monokai.tmTheme` or `bundled:Nord`
+    pub id: String,
+    pub name: String,
+    #[ts(optional)]
+    pub author: Option<String>,
+    /// Format of the theme file, bundled themes are precompiled
+    #[ts(optional)]
+    pub format: Option<ThemeFormat>,
+    pub origin: ThemeOrigin,
+    #[ts(optional)]
+    pub path: Option<PathBuf>,
+    pub appearance: ThemeAppearance,
+    /// Whether the theme is loaded from a `.hidden-color-scheme` or `.hidden-tmTheme` file
+    pub hidden: bool,
+}
+
+impl ThemeInfo {
+    /// ID of the theme loaded from the file at `path`.
+    pub fn file_id(origin: ThemeOrigin, path: &Path) -> String {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+
+        format!("{origin}:{file_name}")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RegisteredTheme {
+    pub info: ThemeInfo,
+    pub theme: SnytectTheme,
+}
+
+/// All available themes, keyed by their ID.
+#[derive(Debug, Clone, Default)]
+pub struct ThemeRegistry {
+    themes: BTreeMap<String, RegisteredTheme>,
+}
+
+impl ThemeRegistry {
+    /// Creates a registry with syntect's default themes and the bundled themes.
+    pub fn with_bundled() -> ThemeRegistry {
+        let mut registry = ThemeRegistry::default();
+
+        for (key, theme) in ThemeSet::load_defaults()
+            .themes
+            .into_iter()
+            .chain(bundled::themes().themes)
+        {
+            registry.insert(
+                ThemeInfo {
+                    id: format!("{}:{key}", ThemeOrigin::Bundled),
+                    // NOTE: Bundled themes have always been listed by their key, which settings
+                    // and presets refer to.
+                    name: key,
+                    author: theme.author.clone(),
+                    format: None,
+                    origin: ThemeOrigin::Bundled,
+                    path: None,
+                    appearance: ThemeAppearance::of(&theme),
+                    hidden: false,
+                },
+                theme,
+            );
+        }
+
+        registry
+    }
+
+    /// Adds a theme, replacing the theme with the same ID.
+    pub fn insert(&mut self, info: ThemeInfo, theme: SnytectTheme) {
+        self.themes
+            .insert(info.id.clone(), RegisteredTheme { info, theme });
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<RegisteredTheme> {
+        self.themes.remove(id)
+    }
+
+    /// Looks up a theme by ID, or by name for settings and presets that refer to themes by name.
+    ///
+    /// If several themes have the name, user themes are preferred over store and bundled themes.
+    pub fn get(&self, key: &str) -> Option<&RegisteredTheme> {
+        self.themes.get(key).or_else(|| {
+            self.themes
+                .values()
+                .filter(|registered| registered.info.name == key)
+                .max_by_key(|registered| registered.info.origin)
+        })
+    }
+
+    pub fn theme(&self, key: &str) -> Option<&SnytectTheme> {
+        self.get(key).map(|registered| &registered.theme)
+    }
+
+    /// Metadata of every theme, sorted by name.
+    pub fn infos(&self) -> Vec<ThemeInfo> {
+        let mut infos: Vec<ThemeInfo> = self
+            .themes
+            .values()
+            .map(|registered| registered.info.clone())
+            .collect();
+
+        infos.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.id.cmp(&b.id)));
+        infos
+    }
+
+    /// IDs and names that themes can be looked up by.
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .themes
+            .values()
+            .flat_map(|registered| [registered.info.id.clone(), registered.info.name.clone()])
+            .collect();
+
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+}
+
+/// Origin of the theme file at `path`, store themes are kept in [crate::dir::store_theme_dir].
+pub fn file_origin(path: &Path, store_dir: &Path) -> ThemeOrigin {
+    if path.parent() == Some(store_dir) {
+        ThemeOrigin::Store
+    } else {
+        ThemeOrigin::User
+    }
+}
+
+/// Loads the bundled themes and every theme file, merging overrides into the themes they
+/// override.
+///
+/// Returns the registry and a report of every file, including the ones that failed to load.
+pub fn load_themes(
+    theme_files: &HashMap<PathBuf, ThemeFormat>,
+    store_dir: &Path,
+) -> (ThemeRegistry, ThemeLoadReport) {
+    let mut registry = ThemeRegistry::with_bundled();
+    let mut report = ThemeLoadReport::default();
+    let overrides = ThemeOverrides::find(theme_files, store_dir);
+
+    for (path, format) in theme_files.iter() {
+        if let Some(base) = overrides.bases_of(path).first() {
+            report.insert(ThemeFileReport::merged(path, format, base));
+            continue;
+        }
+
+        let result = load_theme_with_overrides(path, format, overrides.of(path));
+        report.insert(ThemeFileReport::new(path, format, &result));
+
+        match result {
+            Ok(loaded) => {
+                let origin = file_origin(path, store_dir);
+                registry.insert(loaded.info(path, format, origin), loaded.theme);
+            }
+            Err(err) => error!(
+                "Failed to load theme {}: {}",
+                path.display(),
+                err.chain().join(": ")
+            ),
+        }
+    }
+
+    (registry, report)
+}
+
+/// Parses a single theme file.
+///
+/// Themes without a name are named after the file.
+pub fn load_theme(path: &Path, format: &ThemeFormat) -> Result<LoadedTheme, ThemeError> {
+    load_theme_with_overrides(path, format, &[])
+}
+
+/// Parses a theme file and merges the Sublime color schemes in `overrides` into it, see
+/// [ThemeOverrides].
+pub fn load_theme_with_overrides(
+    path: &Path,
+    format: &ThemeFormat,
+    overrides: &[PathBuf],
+) -> Result<LoadedTheme, ThemeError> {
+    let file_name = || {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default()
+    };
+
+    let convert = |color_scheme: ColorScheme| -> Result<_, ThemeError> {
+        let name = color_scheme.name.clone().unwrap_or_else(file_name);
+        let warnings = color_scheme.warnings();
+
+        Ok((name, SnytectTheme::try_from(color_scheme)?, warnings))
+    };
+
+    let (name, theme, warnings) = match format {
+        ThemeFormat::VsCode => {
+            let vscode_theme = syntect_vscode::parse_vscode_theme_file(path)?;
+            let name = vscode_theme.name.clone().unwrap_or_else(file_name);
+            let warnings = vscode_theme.warnings();
+
+            (name, SnytectTheme::try_from(vscode_theme)?, warnings)
+        }
+        ThemeFormat::Sublime => convert(sublime_color_scheme::parse_color_scheme_with_overrides(
+            path, overrides,
+        )?)?,
+        // NOTE: Overrides are Sublime color schemes, so the tmTheme is converted to one first.
+        ThemeFormat::TmTheme if !overrides.is_empty() => {
+            let mut color_scheme = ColorScheme::from_theme(&ThemeSet::get_theme(path)?);
+
+            for override_path in overrides {
+                color_scheme.merge_file(override_path)?;
+            }
+
+            convert(color_scheme)?
+        }
+        ThemeFormat::TmTheme => {
+            let theme = ThemeSet::get_theme(path)?;
+            let name = theme.name.clone().unwrap_or_else(file_name);
+
+            (name, theme, Vec::new())
+        }
+    };
+
+    for warning in &warnings {
+        warn!("{}: {warning}", path.display());
+    }
+
+    Ok(LoadedTheme {
+        name,
+        theme,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_broken_theme_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+
+        let broken = dir.join("broken.sublime-color-scheme");
+        std::fs::write(
+            &broken,
+            "{\n  \"globals\": {\n    \"background\": #fff\n  }\n}",
+        )
+        .unwrap();
+
+        let partial = dir.join("partial.sublime-color-scheme");
+        std::fs::write(
+            &partial,
+            r##"{ "globals": { "background": "#zzz" }, "rules": [] }"##,
+        )
+        .unwrap();
+
+        let theme_files = HashMap::from([
+            (broken.clone(), ThemeFormat::Sublime),
+            (partial.clone(), ThemeFormat::Sublime),
+        ]);
+        let (registry, report) = load_themes(&theme_files, &dir.join("store"));
+
+        assert!(registry.get("user:partial.sublime-color-scheme").is_some());
+        assert!(registry.get("user:broken.sublime-color-scheme").is_none());
+        assert_eq!(report.files.len(), 2);
+
+        let ThemeFileStatus::Failed { errors, location } = &report.files[0].status else {
+            panic!("Expected {} to fail", broken.display());
+        };
+        assert_eq!(errors[0], "Failed to load Sublime color scheme");
+        assert!(errors.len() > 1);
+        assert_eq!(location.map(|location| location.line), Some(3));
+
+        let ThemeFileStatus::Loaded { name, warnings } = &report.files[1].status else {
+            panic!("Expected {} to load", partial.display());
+        };
+        assert_eq!(name, "partial");
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn merge_user_overrides() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        let store_dir = dir.join("store");
+        std::fs::create_dir_all(&store_dir).unwrap();
+
+        let base = store_dir.join("Mono.sublime-color-scheme");
+        std::fs::write(
+            &base,
+            r##"{
+                "name": "Mono",
+                "variables": { "fg": "#000000" },
+                "globals": { "background": "#ffffff", "foreground": "var(fg)" },
+                "rules": []
+            }"##,
+        )
+        .unwrap();
+
+        let user = dir.join("Mono.sublime-color-scheme");
+        std::fs::write(&user, r##"{ "variables": { "fg": "#ff0000" } }"##).unwrap();
+
+        let theme_files = HashMap::from([
+            (base.clone(), ThemeFormat::Sublime),
+            (user.clone(), ThemeFormat::Sublime),
+        ]);
+        let (registry, report) = load_themes(&theme_files, &store_dir);
+
+        assert!(registry.get("user:Mono.sublime-color-scheme").is_none());
+
+        let merged = registry.get("store:Mono.sublime-color-scheme").unwrap();
+        assert_eq!(
+            merged.theme.settings.foreground.map(|color| color.r),
+            Some(255)
+        );
+
+        let ThemeFileStatus::Merged { into } = &report.files[0].status else {
+            panic!("Expected {} to be merged", user.display());
+        };
+        assert_eq!(into, &base);
+    }
+
+    #[test]
+    fn load_hidden_themes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        let store_dir = dir.join("store");
+        std::fs::create_dir_all(&store_dir).unwrap();
+
+        let base = store_dir.join("Ocean.hidden-tmTheme");
+        std::fs::write(
+            &base,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>name</key>
+    <string>Ocean</string>
+    <key>settings</key>
+    <array>
+        <dict>
+            <key>settings</key>
+            <dict>
+                <key>background</key>
+                <string>#002B36</string>
+                <key>foreground</key>
+                <string>#839496</string>
+            </dict>
+        </dict>
+    </array>
+</dict>
+</plist>
+"#,
+        )
+        .unwrap();
+
+        let user = dir.join("Ocean.sublime-color-scheme");
+        std::fs::write(&user, r##"{ "globals": { "foreground": "#ff0000" } }"##).unwrap();
+
+        let hidden = dir.join("Mono.hidden-color-scheme");
+        std::fs::write(
+            &hidden,
+            r##"{ "globals": { "background": "#ffffff", "foreground": "#000000" }, "rules": [] }"##,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            ThemeFormat::from_path(&base),
+            Some(ThemeFormat::TmTheme)
+        ));
+        assert!(matches!(
+            ThemeFormat::from_path(&hidden),
+            Some(ThemeFormat::Sublime)
+        ));
+
+        let theme_files = HashMap::from([
+            (base.clone(), ThemeFormat::TmTheme),
+            (user.clone(), ThemeFormat::Sublime),
+            (hidden.clone(), ThemeFormat::Sublime),
+        ]);
+
+        let overrides = ThemeOverrides::find(&theme_files, &store_dir);
+        assert_eq!(overrides.of(&base), [user.clone()]);
+
+        let (registry, _) = load_themes(&theme_files, &store_dir);
+
+        let ocean = registry.get("store:Ocean.hidden-tmTheme").unwrap();
+        assert!(ocean.info.hidden);
+        assert_eq!(
+            ocean.theme.settings.foreground.map(|color| color.r),
+            Some(255)
+        );
+
+        let mono = registry.get("user:Mono.hidden-color-scheme").unwrap();
+        assert!(mono.info.hidden);
+        assert_eq!(mono.info.name, "Mono");
+        assert!(!registry.get("base16-ocean.dark").unwrap().info.hidden);
+    }
+
+    #[test]
+    fn look_up_themes_by_id_and_name() {
+        let mut registry = ThemeRegistry::with_bundled();
+        let bundled = registry.get("base16-ocean.dark").unwrap().info.clone();
+
+        assert_eq!(bundled.id, "bundled:base16-ocean.dark");
+        assert_eq!(bundled.appearance, ThemeAppearance::Dark);
+
+        let light = registry.get("bundled:InspiredGitHub").unwrap();
+        assert_eq!(light.info.appearance, ThemeAppearance::Light);
+
+        let path = Path::new("themes/ocean.tmTheme");
+        let loaded = LoadedTheme {
+            name: bundled.name.clone(),
+            theme: registry.theme(&bundled.id).unwrap().clone(),
+            warnings: Vec::new(),
+        };
+        registry.insert(
+            loaded.info(path, &ThemeFormat::TmTheme, ThemeOrigin::User),
+            loaded.theme,
+        );
+
+        assert_eq!(
+            registry.get(&bundled.name).unwrap().info.id,
+            "user:ocean.tmTheme"
+        );
+        assert!(registry.get(&bundled.id).is_some());
+    }
+}
@@ -0,0 +1,78 @@
+//! User overrides of installed color schemes.
+//!
+//! Like a color scheme in Sublime Text's `Packages/User`, a Sublime color scheme in the user theme
+//! directory with the same name as a Sublime color scheme or tmTheme in the store directory is
+//! merged into it instead of being loaded on its own. Hidden variants, e.g.
+//! `.hidden-color-scheme`, are matched by the same name and can be both.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use super::{file_origin, ThemeOrigin};
+use crate::ThemeFormat;
+
+/// Override files by the path of the installed theme they are merged into.
+#[derive(Debug, Clone, Default)]
+pub struct ThemeOverrides {
+    overrides: HashMap<PathBuf, Vec<PathBuf>>,
+}
+
+impl ThemeOverrides {
+    /// Pairs the user files in `theme_files` with the store themes of the same name.
+    pub fn find(theme_files: &HashMap<PathBuf, ThemeFormat>, store_dir: &Path) -> ThemeOverrides {
+        let mut overrides: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+
+        let files = |origin: ThemeOrigin| {
+            theme_files
+                .iter()
+                .filter(move |(path, _)| file_origin(path, store_dir) == origin)
+        };
+
+        for (path, format) in files(ThemeOrigin::User) {
+            if !matches!(format, ThemeFormat::Sublime) {
+                continue;
+            }
+
+            for (base, base_format) in files(ThemeOrigin::Store) {
+                if matches!(base_format, ThemeFormat::Sublime | ThemeFormat::TmTheme)
+                    && base.file_stem() == path.file_stem()
+                {
+                    overrides
+                        .entry(base.clone())
+                        .or_default()
+                        .push(path.clone());
+                }
+            }
+        }
+
+        // NOTE: Sorted so overrides are merged in the same order on every load.
+        for paths in overrides.values_mut() {
+            paths.sort();
+        }
+
+        ThemeOverrides { overrides }
+    }
+
+    /// Override files of the theme at `path`, in the order they are merged.
+    pub fn of(&self, path: &Path) -> &[PathBuf] {
+        self.overrides
+            .get(path)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Installed themes the file at `path` is merged into, none if it isn't an override.
+    pub fn bases_of(&self, path: &Path) -> Vec<&Path> {
+        let mut bases: Vec<&Path> = self
+            .overrides
+            .iter()
+            .filter(|(_, paths)| paths.iter().any(|override_path| override_path == path))
+            .map(|(base, _)| base.as_path())
+            .collect();
+
+        bases.sort();
+        bases
+    }
+}
@@ -0,0 +1,263 @@
+//! Light and dark variants of the same theme.
+//!
+//! Pairs chosen by the user are stored in a TOML file as an array of tables:
+//!
+//! ```toml
+//! [[pair]]
+//! light = "Catppuccin Latte"
+//! dark = "Catppuccin Mocha"
+//! ```
+//!
+//! Themes without a stored pair are paired with the theme of the same family and the opposite
+//! appearance, e.g. `Solarized (light)` and `Solarized (dark)`.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Table};
+use ts_rs::TS;
+
+use super::{luminance, RegisteredTheme, ThemeAppearance, ThemeRegistry};
+
+pub const THEME_PAIRS_FILE: &str = "theme-pairs.toml";
+
+/// Words that name the variant of a theme rather than its family.
+const VARIANT_WORDS: &[&str] = &[
+    "light",
+    "dark",
+    "day",
+    "night",
+    "dawn",
+    "dusk",
+    "latte",
+    "frappe",
+    "macchiato",
+    "mocha",
+    "mirage",
+    "moon",
+    "storm",
+];
+
+#[derive(Debug, Error)]
+pub enum ThemePairError {
+    #[error("Theme \"{0}\" does not exist")]
+    NotFound(String),
+    #[error("Theme \"{theme}\" is not a {expected:?} theme")]
+    WrongAppearance {
+        theme: String,
+        expected: ThemeAppearance,
+    },
+    #[error("Theme pair {0} is missing \"light\" or \"dark\"")]
+    InvalidPair(usize),
+    #[error("Failed to access theme pairs: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to parse theme pairs: {0}")]
+    Parse(#[from] toml_edit::TomlError),
+}
+
+/// A light and a dark theme, referred to by ID or name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ThemePair {
+    pub light: String,
+    pub dark: String,
+}
+
+/// Reads the pairs stored in `path`, a missing file has no pairs.
+pub fn load_pairs(path: &Path) -> Result<Vec<ThemePair>, ThemePairError> {
+    let document: DocumentMut = match std::fs::read_to_string(path) {
+        Ok(content) => content.parse()?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let Some(pairs) = document.get("pair").and_then(Item::as_array_of_tables) else {
+        return Ok(Vec::new());
+    };
+
+    pairs
+        .iter()
+        .enumerate()
+        .map(|(index, table)| {
+            let theme = |key| table.get(key).and_then(Item::as_str).map(str::to_string);
+
+            Ok(ThemePair {
+                light: theme("light").ok_or(ThemePairError::InvalidPair(index))?,
+                dark: theme("dark").ok_or(ThemePairError::InvalidPair(index))?,
+            })
+        })
+        .collect()
+}
+
+/// Replaces the pairs stored in `path`, keeping the comments at the top of the file.
+pub fn save_pairs(path: &Path, pairs: &[ThemePair]) -> Result<(), ThemePairError> {
+    let mut document: DocumentMut = match std::fs::read_to_string(path) {
+        Ok(content) => content.parse()?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => DocumentMut::new(),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut tables = ArrayOfTables::new();
+
+    for pair in pairs {
+        let mut table = Table::new();
+        table.insert("light", pair.light.as_str().into());
+        table.insert("dark", pair.dark.as_str().into());
+        tables.push(table);
+    }
+
+    document.insert("pair", Item::ArrayOfTables(tables));
+    std::fs::write(path, document.to_string())?;
+
+    Ok(())
+}
+
+impl ThemeRegistry {
+    /// Checks that both themes of a pair exist and have the expected appearance.
+    pub fn validate_pair(&self, pair: &ThemePair) -> Result<(), ThemePairError> {
+        for (key, expected) in [
+            (&pair.light, ThemeAppearance::Light),
+            (&pair.dark, ThemeAppearance::Dark),
+        ] {
+            let registered = self
+                .get(key)
+                .ok_or_else(|| ThemePairError::NotFound(key.clone()))?;
+
+            if registered.info.appearance != expected {
+                return Err(ThemePairError::WrongAppearance {
+                    theme: key.clone(),
+                    expected,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finds the variant of the theme `key` with the opposite appearance.
+    ///
+    /// Stored pairs take precedence, otherwise the theme of the same family with the most
+    /// contrasting background is picked, e.g. `Catppuccin Mocha` for `Catppuccin Latte`.
+    pub fn counterpart(&self, key: &str, pairs: &[ThemePair]) -> Option<&RegisteredTheme> {
+        let theme = self.get(key)?;
+        let id = theme.info.id.as_str();
+        let resolve = |key: &str| self.get(key).map(|registered| registered.info.id.as_str());
+
+        let stored = pairs.iter().find_map(|pair| {
+            if resolve(&pair.light) == Some(id) {
+                self.get(&pair.dark)
+            } else if resolve(&pair.dark) == Some(id) {
+                self.get(&pair.light)
+            } else {
+                None
+            }
+        });
+
+        if stored.is_some() {
+            return stored;
+        }
+
+        let theme_family = family(&theme.info.name);
+
+        if theme_family.is_empty() {
+            return None;
+        }
+
+        let background = |registered: &RegisteredTheme| {
+            registered
+                .theme
+                .settings
+                .background
+                .map(luminance)
+                .unwrap_or_default()
+        };
+
+        let candidates = self.themes.values().filter(|candidate| {
+            candidate.info.appearance != theme.info.appearance
+                && family(&candidate.info.name) == theme_family
+        });
+
+        match theme.info.appearance {
+            ThemeAppearance::Light => {
+                candidates.min_by(|a, b| background(a).total_cmp(&background(b)))
+            }
+            ThemeAppearance::Dark => {
+                candidates.max_by(|a, b| background(a).total_cmp(&background(b)))
+            }
+        }
+    }
+
+    /// Returns the light and dark variant of the theme `key`, in that order.
+    pub fn pair(
+        &self,
+        key: &str,
+        pairs: &[ThemePair],
+    ) -> Option<(&RegisteredTheme, &RegisteredTheme)> {
+        let theme = self.get(key)?;
+        let counterpart = self.counterpart(key, pairs)?;
+
+        match theme.info.appearance {
+            ThemeAppearance::Light => Some((theme, counterpart)),
+            ThemeAppearance::Dark => Some((counterpart, theme)),
+        }
+    }
+}
+
+/// Name of a theme without the words naming its variant, e.g. `solarized` for
+/// `Solarized (dark)`.
+fn family(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty() && !VARIANT_WORDS.contains(word))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pair_light_and_dark_themes() {
+        let registry = ThemeRegistry::with_bundled();
+
+        let (light, dark) = registry.pair("Solarized (dark)", &[]).unwrap();
+        assert_eq!(light.info.name, "Solarized (light)");
+        assert_eq!(dark.info.name, "Solarized (dark)");
+
+        let pairs = vec![ThemePair {
+            light: "InspiredGitHub".to_string(),
+            dark: "bundled:base16-ocean.dark".to_string(),
+        }];
+        registry.validate_pair(&pairs[0]).unwrap();
+        assert!(registry
+            .validate_pair(&ThemePair {
+                light: "base16-ocean.dark".to_string(),
+                dark: "InspiredGitHub".to_string(),
+            })
+            .is_err());
+
+        let counterpart = registry.counterpart("base16-ocean.dark", &pairs).unwrap();
+        assert_eq!(counterpart.info.name, "InspiredGitHub");
+        assert!(registry.counterpart("InspiredGitHub", &[]).is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pairs.toml");
+        std::fs::write(&path, "# My pairs\n").unwrap();
+        save_pairs(&path, &pairs).unwrap();
+        let loaded = load_pairs(&path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+
+        assert_eq!(loaded, pairs);
+        assert!(content.starts_with("# My pairs"));
+    }
+
+    #[test]
+    fn strip_variant_words() {
+        assert_eq!(family("Solarized (light)"), "solarized");
+        assert_eq!(family("base16-ocean.dark"), "base16 ocean");
+        assert_eq!(family("Catppuccin Macchiato"), "catppuccin");
+    }
+}
@@ -18,3 +18,22 @@ pub async fn send_async_channel<T>(channel: &async_channel::Sender<T>, value: T)
         log::warn!("Failed to send async channel: {}", err);
     }
 }
+
+/// Sets a value in a TOML table, keeping the comments and formatting of an existing value.
+///
+/// # Arguments
+///
+/// * `table` - The table to set the value in.
+/// * `key` - The key of the value.
+/// * `value` - The new value.
+pub fn set_toml_value(table: &mut toml_edit::Table, key: &str, mut value: toml_edit::Value) {
+    match table.get_mut(key).and_then(toml_edit::Item::as_value_mut) {
+        Some(existing) => {
+            *value.decor_mut() = existing.decor().clone();
+            *existing = value;
+        }
+        None => {
+            table.insert(key, toml_edit::value(value));
+        }
+    }
+}
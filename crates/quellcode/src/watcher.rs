@@ -0,0 +1,278 @@
+//! Live reload of themes, syntaxes and settings.
+//!
+//! Changed files are reloaded into [AppState] and the frontend is notified through the
+//! `themes-changed`, `syntaxes-changed`, `settings-changed` and `settings-error` events.
+
+use std::{
+    collections::BTreeSet,
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::{mpsc::channel, Mutex},
+    time::Duration,
+};
+
+use log::{debug, error, info};
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use ts_rs::TS;
+
+use crate::{
+    dir,
+    settings::Settings,
+    syntax::{load_syntaxes, syntax_names_in},
+    theme::{file_origin, load_theme_with_overrides, ThemeFileReport, ThemeInfo, ThemeOverrides},
+    AppState, ThemeFormat,
+};
+
+/// Time to wait for more events before reloading, editors often write a file in several steps.
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct FileError {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl FileError {
+    pub fn new(path: &Path, message: impl Display) -> FileError {
+        FileError {
+            path: path.to_path_buf(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Payload of the `themes-changed` and `syntaxes-changed` events.
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ReloadEvent {
+    /// Names of syntaxes or IDs of themes that were added or reloaded
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+    /// Files that failed to load, previously loaded versions are kept
+    pub errors: Vec<FileError>,
+}
+
+/// Starts watching the theme and syntax directories and the config file.
+pub fn watch(app_handle: &AppHandle) -> notify::Result<()> {
+    let theme_dir = dir::code_theme_dir(app_handle);
+    let store_dir = dir::store_theme_dir(app_handle);
+    let syntax_dir = dir::code_syntax_dir(app_handle);
+    let cache_dir = dir::cache_dir(app_handle);
+    let config_file = dir::config_file(app_handle);
+
+    let (tx, rx) = channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+
+    watcher.watch(&theme_dir, RecursiveMode::NonRecursive)?;
+    watcher.watch(&store_dir, RecursiveMode::NonRecursive)?;
+    watcher.watch(&syntax_dir, RecursiveMode::Recursive)?;
+    // NOTE: Editors replace files instead of writing them in place, so watch the directory.
+    watcher.watch(&dir::config_dir(app_handle), RecursiveMode::NonRecursive)?;
+
+    let app_handle = app_handle.clone();
+
+    std::thread::spawn(move || {
+        // NOTE: The watcher stops when dropped, keep it alive as long as the thread runs.
+        let _watcher = watcher;
+
+        while let Ok(event) = rx.recv() {
+            let mut paths = BTreeSet::new();
+            collect_paths(event, &mut paths);
+
+            while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+                collect_paths(event, &mut paths);
+            }
+
+            debug!("Files changed: {paths:?}");
+
+            let theme_paths: BTreeSet<PathBuf> = paths
+                .iter()
+                .filter(|path| {
+                    path.parent() == Some(theme_dir.as_path())
+                        || path.parent() == Some(store_dir.as_path())
+                })
+                .cloned()
+                .collect();
+
+            if !theme_paths.is_empty() {
+                let event = reload_themes(&app_handle, &theme_paths, &store_dir);
+                let _ = app_handle.emit("themes-changed", event);
+            }
+
+            let syntax_paths: BTreeSet<PathBuf> = paths
+                .iter()
+                .filter(|path| path.starts_with(&syntax_dir))
+                .cloned()
+                .collect();
+
+            if !syntax_paths.is_empty() {
+                let event = reload_syntaxes(&app_handle, &syntax_paths, &syntax_dir, &cache_dir);
+                let _ = app_handle.emit("syntaxes-changed", event);
+            }
+
+            if paths.contains(&config_file) {
+                reload_settings(&app_handle, &config_file);
+            }
+        }
+    });
+
+    Ok(())
+}
+
+fn collect_paths(event: notify::Result<notify::Event>, paths: &mut BTreeSet<PathBuf>) {
+    match event {
+        Ok(event) if matches!(event.kind, EventKind::Access(_)) => {}
+        Ok(event) => paths.extend(event.paths),
+        Err(err) => error!("Failed to watch files: {err}"),
+    }
+}
+
+fn reload_themes(
+    app_handle: &AppHandle,
+    paths: &BTreeSet<PathBuf>,
+    store_dir: &Path,
+) -> ReloadEvent {
+    let mut event = ReloadEvent::default();
+
+    // NOTE: Overrides are merged into the theme they override, so a change to either reloads
+    // both, and removing a theme turns its overrides back into themes of their own.
+    let theme_files = crate::code_theme_files(app_handle);
+    let overrides = ThemeOverrides::find(&theme_files, store_dir);
+    let paths: BTreeSet<PathBuf> = paths
+        .iter()
+        .flat_map(|path| {
+            theme_files
+                .keys()
+                .filter(move |other| other.file_stem() == path.file_stem())
+                .cloned()
+                .chain([path.clone()])
+        })
+        .collect();
+
+    // NOTE: Parse before locking the state, themes can take a while to load.
+    let loaded: Vec<_> = paths
+        .iter()
+        .map(|path| {
+            let format = ThemeFormat::from_path(path).filter(|_| path.is_file());
+            let result = format.as_ref().map(|format| {
+                if let Some(base) = overrides.bases_of(path).first() {
+                    return (ThemeFileReport::merged(path, format, base), None, true);
+                }
+
+                let result = load_theme_with_overrides(path, format, overrides.of(path));
+                let report = ThemeFileReport::new(path, format, &result);
+
+                let theme = result
+                    .inspect_err(|err| {
+                        let message = err.chain().join(": ");
+                        error!("Failed to load theme {}: {message}", path.display());
+                        event.errors.push(FileError::new(path, message));
+                    })
+                    .ok();
+
+                (report, theme, false)
+            });
+
+            (path, format, result)
+        })
+        .collect();
+
+    let state = app_handle.state::<Mutex<AppState>>();
+    let mut state = state.lock().expect("Failed to lock state");
+
+    for (path, format, result) in loaded {
+        let origin = file_origin(path, store_dir);
+        let id = ThemeInfo::file_id(origin, path);
+
+        let (Some(format), Some((report, theme, merged))) = (format, result) else {
+            state.theme_files.remove(path);
+            state.theme_report.remove(path);
+
+            if state.themes.remove(&id).is_some() {
+                event.removed.push(id);
+            }
+
+            continue;
+        };
+
+        state.theme_report.insert(report);
+
+        if merged {
+            // NOTE: The override was a theme of its own until the theme it overrides was added.
+            if state.themes.remove(&id).is_some() {
+                event.removed.push(id);
+            }
+        } else if let Some(loaded) = theme {
+            info!("Reloaded theme {} from {}", loaded.name, path.display());
+
+            let info = loaded.info(path, &format, origin);
+            state.themes.insert(info, loaded.theme);
+            event.changed.push(id);
+        }
+
+        state.theme_files.insert(path.clone(), format);
+    }
+
+    event
+}
+
+fn reload_syntaxes(
+    app_handle: &AppHandle,
+    paths: &BTreeSet<PathBuf>,
+    syntax_dir: &Path,
+    cache_dir: &Path,
+) -> ReloadEvent {
+    let (syntax_set, errors) = load_syntaxes(syntax_dir, Some(cache_dir));
+    let modified = syntax_names_in(paths);
+
+    let state = app_handle.state::<Mutex<AppState>>();
+    let mut state = state.lock().expect("Failed to lock state");
+
+    let previous: BTreeSet<String> = state.syntax_names().into_iter().collect();
+    state.syntect_syntaxes = syntax_set;
+    state.syntax_errors = errors.clone();
+    let current: BTreeSet<String> = state.syntax_names().into_iter().collect();
+
+    info!("Reloaded {} syntaxes", current.len());
+
+    // NOTE: Names only tell added syntaxes apart, modified files are looked up by path.
+    let changed = current
+        .iter()
+        .filter(|name| !previous.contains(*name) || modified.contains(*name))
+        .cloned()
+        .collect();
+
+    ReloadEvent {
+        changed,
+        removed: previous.difference(&current).cloned().collect(),
+        errors,
+    }
+}
+
+fn reload_settings(app_handle: &AppHandle, config_file: &Path) {
+    let mut settings = match Settings::load_or_create(config_file) {
+        Ok(settings) => settings,
+        Err(err) => {
+            error!("Failed to reload settings: {err}");
+            let _ = app_handle.emit("settings-error", FileError::new(config_file, err));
+            return;
+        }
+    };
+
+    let state = app_handle.state::<Mutex<AppState>>();
+    let mut state = state.lock().expect("Failed to lock state");
+
+    settings.validate(&state.themes.keys(), &state.syntax_names());
+
+    // NOTE: Saving settings from the app also triggers a reload, only notify on real changes.
+    if settings != state.settings {
+        info!("Reloaded settings from {}", config_file.display());
+        state.settings = settings.clone();
+        let _ = app_handle.emit("settings-changed", settings);
+    }
+}
@@ -0,0 +1,254 @@
+//! Color schemes with their colors parsed but not evaluated.
+//!
+//! Unlike converting to a [syntect::highlighting::Theme], the parsed colors keep their
+//! variable references and adjusters, so a variable can be changed and every color depending
+//! on it evaluated again.
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use syntect::highlighting::Color as SyntectColor;
+
+use crate::{
+    color::{VariableResolver, Variables},
+    error::ParseError,
+    parser::{parse_color, Color},
+    ColorScheme, RuleColor, GLOBAL_COLORS,
+};
+
+/// Where a color is used in a color scheme.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColorLocation {
+    Variable(String),
+    Global(String),
+    /// A color of the rule at `index` in [ColorScheme::rules]
+    Rule {
+        index: usize,
+        property: RuleProperty,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RuleProperty {
+    /// The foreground color at the index, more than one for hashed foregrounds
+    Foreground(usize),
+    /// The background color at the index, more than one for gradients
+    Background(usize),
+    SelectionForeground,
+}
+
+/// The parsed colors of a [Rule](crate::Rule).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleColors {
+    pub foreground: Vec<Color>,
+    pub background: Vec<Color>,
+    pub selection_foreground: Option<Color>,
+}
+
+/// The colors of a [ColorScheme], parsed but not evaluated.
+///
+/// Only globals that hold colors are included, options and CSS are left in the scheme.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColorSchemeAst {
+    pub variables: HashMap<String, Color>,
+    /// Variables that aren't colors, such as font styles
+    pub values: HashMap<String, String>,
+    pub globals: HashMap<String, Color>,
+    /// The colors of each rule, in the same order as [ColorScheme::rules]
+    pub rules: Vec<RuleColors>,
+}
+
+impl ColorScheme {
+    /// Parses every color of the scheme without evaluating it.
+    pub fn ast(&self) -> Result<ColorSchemeAst, ParseError> {
+        let parse = |path: String, input: &str| {
+            parse_color(input).map_err(|err| self.value_error(path, input, err))
+        };
+
+        let parse_all =
+            |path: String, color: &Option<RuleColor>| -> Result<Vec<Color>, ParseError> {
+                match color {
+                    None => Ok(Vec::new()),
+                    Some(RuleColor::Single(input)) => Ok(vec![parse(path, input)?]),
+                    Some(RuleColor::Multiple(inputs)) => inputs
+                        .iter()
+                        .enumerate()
+                        .map(|(index, input)| parse(format!("{path}[{index}]"), input))
+                        .collect(),
+                }
+            };
+
+        let mut variables = HashMap::new();
+        let mut values = HashMap::new();
+
+        for (name, value) in self.variables.iter().flatten() {
+            // NOTE: Variables can hold any value, only references to them need to be colors.
+            match parse_color(value) {
+                Ok(color) => variables.insert(name.clone(), color),
+                Err(_) => values.insert(name.clone(), value.clone()),
+            };
+        }
+
+        Ok(ColorSchemeAst {
+            variables,
+            values,
+            globals: self
+                .globals
+                .iter()
+                .filter(|(key, _)| GLOBAL_COLORS.contains(&key.as_str()))
+                .map(|(key, value)| Ok((key.clone(), parse(format!("globals.{key}"), value)?)))
+                .collect::<Result<_, ParseError>>()?,
+            rules: self
+                .rules
+                .iter()
+                .enumerate()
+                .map(|(index, rule)| {
+                    Ok(RuleColors {
+                        foreground: parse_all(
+                            format!("rules[{index}].foreground"),
+                            &rule.foreground,
+                        )?,
+                        background: parse_all(
+                            format!("rules[{index}].background"),
+                            &rule.background,
+                        )?,
+                        selection_foreground: rule
+                            .selection_foreground
+                            .as_deref()
+                            .map(|input| {
+                                parse(format!("rules[{index}].selection_foreground"), input)
+                            })
+                            .transpose()?,
+                    })
+                })
+                .collect::<Result<_, ParseError>>()?,
+        })
+    }
+}
+
+impl ColorSchemeAst {
+    /// Returns the color at `location`.
+    pub fn get(&self, location: &ColorLocation) -> Option<&Color> {
+        match location {
+            ColorLocation::Variable(name) => self.variables.get(name),
+            ColorLocation::Global(key) => self.globals.get(key),
+            ColorLocation::Rule { index, property } => {
+                let rule = self.rules.get(*index)?;
+
+                match property {
+                    RuleProperty::Foreground(index) => rule.foreground.get(*index),
+                    RuleProperty::Background(index) => rule.background.get(*index),
+                    RuleProperty::SelectionForeground => rule.selection_foreground.as_ref(),
+                }
+            }
+        }
+    }
+
+    /// Returns a resolver that evaluates each variable at most once, use it to evaluate many
+    /// colors.
+    pub fn resolver(&self) -> VariableResolver<'_> {
+        VariableResolver::new(self)
+    }
+
+    /// Evaluates `color`, resolving the variables it references only when they are needed.
+    pub fn evaluate(&self, color: &Color) -> Result<SyntectColor, ParseError> {
+        self.resolver().evaluate(color)
+    }
+
+    /// Evaluates the color at `location`.
+    pub fn evaluate_at(
+        &self,
+        location: &ColorLocation,
+    ) -> Option<Result<SyntectColor, ParseError>> {
+        self.get(location).map(|color| self.evaluate(color))
+    }
+
+    /// Returns every location of a color in the scheme.
+    pub fn locations(&self) -> Vec<ColorLocation> {
+        let mut locations: Vec<ColorLocation> = self
+            .variables
+            .keys()
+            .map(|name| ColorLocation::Variable(name.clone()))
+            .chain(
+                self.globals
+                    .keys()
+                    .map(|key| ColorLocation::Global(key.clone())),
+            )
+            .collect();
+
+        for (index, rule) in self.rules.iter().enumerate() {
+            let properties = (0..rule.foreground.len())
+                .map(RuleProperty::Foreground)
+                .chain((0..rule.background.len()).map(RuleProperty::Background))
+                .chain(
+                    rule.selection_foreground
+                        .iter()
+                        .map(|_| RuleProperty::SelectionForeground),
+                );
+
+            locations.extend(properties.map(|property| ColorLocation::Rule { index, property }));
+        }
+
+        locations.sort();
+        locations
+    }
+
+    /// Maps every variable to the locations that reference it directly.
+    pub fn references(&self) -> HashMap<String, Vec<ColorLocation>> {
+        let mut references: HashMap<String, Vec<ColorLocation>> = HashMap::new();
+
+        for location in self.locations() {
+            let Some(color) = self.get(&location) else {
+                continue;
+            };
+
+            for variable in color.variables() {
+                references
+                    .entry(variable.to_string())
+                    .or_default()
+                    .push(location.clone());
+            }
+        }
+
+        references
+    }
+
+    /// Returns the locations whose color changes when the variable `name` changes, including
+    /// colors that reference it through other variables.
+    pub fn dependents(&self, name: &str) -> Vec<ColorLocation> {
+        let references = self.references();
+        let mut dependents = BTreeSet::new();
+        let mut queue = VecDeque::from([name.to_string()]);
+
+        while let Some(variable) = queue.pop_front() {
+            for location in references.get(&variable).into_iter().flatten() {
+                // NOTE: The set also stops variables that reference each other from looping.
+                if !dependents.insert(location.clone()) {
+                    continue;
+                }
+
+                if let ColorLocation::Variable(dependent) = location {
+                    queue.push_back(dependent.clone());
+                }
+            }
+        }
+
+        dependents.into_iter().collect()
+    }
+}
+
+impl Variables for ColorSchemeAst {
+    fn variable(&self, name: &str) -> Result<Color, ParseError> {
+        match (self.variables.get(name), self.values.get(name)) {
+            (Some(color), _) => Ok(color.clone()),
+            (None, Some(value)) => parse_color(value),
+            (None, None) => Err(ParseError::UnknownVariable(name.to_string())),
+        }
+    }
+
+    fn text(&self, name: &str) -> Option<String> {
+        self.values
+            .get(name)
+            .cloned()
+            .or_else(|| self.variables.get(name).map(ToString::to_string))
+    }
+}
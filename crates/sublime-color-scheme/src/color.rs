@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap};
 
 use crate::{
     error::ParseError,
@@ -7,11 +7,175 @@ use crate::{
 
 use log::debug;
 use palette::{
-    color_difference::Wcag21RelativeContrast, Darken, Desaturate, Hsl, Hsla, Hwb, Hwba, IntoColor,
-    Lighten, LinSrgba, Mix, Saturate, Srgba, WithAlpha,
+    color_difference::Wcag21RelativeContrast,
+    white_point::{D50, D65},
+    Darken, Desaturate, Hsl, Hsla, Hwb, Hwba, IntoColor, Lab, Lch, Lighten, LinSrgba, Mix, Oklab,
+    Oklch, Saturate, Srgb, Srgba, WithAlpha, Xyz,
 };
 use syntect::highlighting::Color as SyntectColor;
 
+/// Bradford chromatic adaptation from the D50 white point of `lab()` and `lch()` to the D65
+/// white point of sRGB, as specified by CSS Color Level 4.
+const D50_TO_D65: [[f64; 3]; 3] = [
+    [0.955473421488075, -0.023098454948765, 0.063259243200571],
+    [-0.028369709333864, 1.0099953980813, 0.021041441191917],
+    [0.012314014864482, -0.020507649298899, 1.3303659262421],
+];
+
+const D65_TO_D50: [[f64; 3]; 3] = [
+    [1.0479297925450, 0.022946870601610, -0.050192266289205],
+    [0.029627808770056, 0.990434426753880, -0.017073799063419],
+    [-0.009243040646205, 0.015055191490298, 0.751874281428137],
+];
+
+/// Looks up the variables referenced by colors.
+pub trait Variables {
+    /// Returns the parsed value of the variable `name`.
+    fn variable(&self, name: &str) -> Result<Color, ParseError>;
+
+    /// Returns the value of the variable `name` as text, for values that aren't colors such as
+    /// font styles.
+    fn text(&self, name: &str) -> Option<String>;
+}
+
+/// Variables as they are written in the color scheme, parsed when they are referenced.
+impl Variables for HashMap<String, String> {
+    fn variable(&self, name: &str) -> Result<Color, ParseError> {
+        let value = self
+            .get(name)
+            .ok_or_else(|| ParseError::UnknownVariable(name.to_string()))?;
+
+        parse_color(value).map_err(|err| ParseError::Variable {
+            name: name.to_string(),
+            source: Box::new(err),
+        })
+    }
+
+    fn text(&self, name: &str) -> Option<String> {
+        self.get(name).cloned()
+    }
+}
+
+/// Variables that are already parsed.
+impl Variables for HashMap<String, Color> {
+    fn variable(&self, name: &str) -> Result<Color, ParseError> {
+        self.get(name)
+            .cloned()
+            .ok_or_else(|| ParseError::UnknownVariable(name.to_string()))
+    }
+
+    fn text(&self, name: &str) -> Option<String> {
+        self.get(name).map(ToString::to_string)
+    }
+}
+
+/// Resolves the variables of a color scheme, evaluating each variable only once.
+///
+/// Variables that reference each other are reported as a [ParseError::VariableCycle] naming
+/// the variables involved.
+pub struct VariableResolver<'a> {
+    variables: &'a dyn Variables,
+    colors: RefCell<HashMap<String, Srgba>>,
+    /// Variables that are being resolved, the innermost last
+    resolving: RefCell<Vec<String>>,
+}
+
+impl<'a> VariableResolver<'a> {
+    pub fn new(variables: &'a dyn Variables) -> VariableResolver<'a> {
+        VariableResolver {
+            variables,
+            colors: RefCell::new(HashMap::new()),
+            resolving: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Parses and evaluates a color.
+    pub fn color(&self, input: &str) -> Result<SyntectColor, ParseError> {
+        self.evaluate(&parse_color(input)?)
+    }
+
+    /// Evaluates a parsed color.
+    pub fn evaluate(&self, input: &Color) -> Result<SyntectColor, ParseError> {
+        let color = get_palette_color(input, self)?;
+
+        // NOTE: Round instead of truncating, so hex colors survive the conversion to floats and
+        // back.
+        let sytent_color = SyntectColor {
+            r: (color.red * 255.0).round() as u8,
+            g: (color.green * 255.0).round() as u8,
+            b: (color.blue * 255.0).round() as u8,
+            a: (color.alpha * 255.0).round() as u8,
+        };
+
+        debug!("Got Color: {:?}", sytent_color);
+        Ok(sytent_color)
+    }
+
+    /// Resolves a value that isn't a color, e.g. a font style, following `var(name)`
+    /// references to other variables.
+    pub fn text(&self, input: &str) -> Result<String, ParseError> {
+        let Some(name) = variable_reference(input) else {
+            return Ok(input.to_string());
+        };
+
+        self.enter(name)?;
+        let result = self
+            .variables
+            .text(name)
+            .ok_or_else(|| ParseError::UnknownVariable(name.to_string()))
+            .and_then(|value| self.text(&value));
+        self.leave();
+
+        result
+    }
+
+    fn variable(&self, name: &str) -> Result<Srgba, ParseError> {
+        if let Some(color) = self.colors.borrow().get(name) {
+            return Ok(*color);
+        }
+
+        self.enter(name)?;
+        let result = self
+            .variables
+            .variable(name)
+            .and_then(|color| get_palette_color(&color, self));
+        self.leave();
+
+        let color = result?;
+        self.colors.borrow_mut().insert(name.to_string(), color);
+
+        Ok(color)
+    }
+
+    /// Marks `name` as being resolved, failing if it is already being resolved further out.
+    fn enter(&self, name: &str) -> Result<(), ParseError> {
+        let mut resolving = self.resolving.borrow_mut();
+
+        if let Some(start) = resolving.iter().position(|variable| variable == name) {
+            let mut chain = resolving[start..].to_vec();
+            chain.push(name.to_string());
+
+            return Err(ParseError::VariableCycle(chain));
+        }
+
+        resolving.push(name.to_string());
+        Ok(())
+    }
+
+    fn leave(&self) {
+        self.resolving.borrow_mut().pop();
+    }
+}
+
+/// Returns the name of the variable if `input` is a `var(name)` reference.
+fn variable_reference(input: &str) -> Option<&str> {
+    let name = input.trim().strip_prefix("var(")?.strip_suffix(')')?.trim();
+
+    name.chars()
+        .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        .then_some(name)
+}
+
 /// Parses a **Sublime Color Scheme** [color](https://www.sublimetext.com/docs/color_schemes.html#colors) and returns a [SyntectColor].
 ///
 /// # Arguments
@@ -25,32 +189,29 @@ pub fn get_color(
     input: &str,
     variables: &HashMap<String, String>,
 ) -> Result<SyntectColor, ParseError> {
-    let input = parse_color(input)?;
-    let color = get_palette_color(input, variables)?;
-
-    let sytent_color = SyntectColor {
-        r: (color.red * 255.0) as u8,
-        g: (color.green * 255.0) as u8,
-        b: (color.blue * 225.0) as u8,
-        a: (color.alpha * 255.0) as u8,
-    };
+    VariableResolver::new(variables).color(input)
+}
 
-    debug!("Got Color: {:?}", sytent_color);
-    Ok(sytent_color)
+/// Evaluates a parsed [Color], resolving the variables it references through `variables`.
+pub fn evaluate_color(
+    input: &Color,
+    variables: &dyn Variables,
+) -> Result<SyntectColor, ParseError> {
+    VariableResolver::new(variables).evaluate(input)
 }
 
 /// Gets the [palette] crate color from [Color].
 ///
 /// Always converts to [Srgba] since [SyntectColor] only supports rgb color space.
 fn get_palette_color(
-    input_color: Color,
-    variables: &HashMap<String, String>,
+    input_color: &Color,
+    resolver: &VariableResolver,
 ) -> Result<Srgba, ParseError> {
     let color: Srgba<f32> = match input_color {
         Color::RGB(red, green, blue) => Srgba::new(
-            red as f32 / 255.0,
-            green as f32 / 255.0,
-            blue as f32 / 255.0,
+            *red as f32 / 255.0,
+            *green as f32 / 255.0,
+            *blue as f32 / 255.0,
             1.0,
         ),
         Color::Hex(hex) => match hex.len() {
@@ -88,31 +249,66 @@ fn get_palette_color(
             _ => return Err(ParseError::InvalidHexColor),
         },
         Color::RGBA(red, green, blue, alpha) => Srgba::new(
-            red as f32 / 255.0,
-            green as f32 / 255.0,
-            blue as f32 / 255.0,
-            alpha,
+            *red as f32 / 255.0,
+            *green as f32 / 255.0,
+            *blue as f32 / 255.0,
+            *alpha,
         ),
         Color::HSL(hue, saturation, lightness) => {
-            Hsl::new(hue as f32, saturation, lightness).into_color()
+            Hsl::new(*hue as f32, *saturation, *lightness).into_color()
         }
         Color::HSLA(hue, saturation, lightness, alpha) => {
-            Hsla::new(hue as f32, saturation, lightness, alpha).into_color()
+            Hsla::new(*hue as f32, *saturation, *lightness, *alpha).into_color()
         }
 
         Color::HWB(hue, whiteness, blackness, alpha) => {
             if let Some(alpha) = alpha {
-                Hwba::new(hue as f32, whiteness, blackness, alpha).into_color()
+                Hwba::new(*hue as f32, *whiteness, *blackness, *alpha).into_color()
             } else {
-                Hwb::new(hue as f32, whiteness, blackness).into_color()
+                Hwb::new(*hue as f32, *whiteness, *blackness).into_color()
             }
         }
-        Color::Variable(name) => {
-            let reference = variables.get(&name).ok_or(ParseError::UnknownVariable)?;
-            let color = parse_color(reference)?;
+        Color::Lab(lightness, a, b, alpha) => {
+            from_lab(Lab::new(*lightness, *a, *b)).with_alpha(alpha.unwrap_or(1.0))
+        }
+        Color::Lch(lightness, chroma, hue, alpha) => {
+            from_lab(Lch::<D50>::new(*lightness, *chroma, *hue).into_color())
+                .with_alpha(alpha.unwrap_or(1.0))
+        }
+        Color::Oklab(lightness, a, b, alpha) => {
+            let color: Srgb = Oklab::new(*lightness, *a, *b).into_color();
+            color.with_alpha(alpha.unwrap_or(1.0))
+        }
+        Color::Oklch(lightness, chroma, hue, alpha) => {
+            let color: Srgb = Oklch::new(*lightness, *chroma, *hue).into_color();
+            color.with_alpha(alpha.unwrap_or(1.0))
+        }
+        Color::Mix(space, first, first_percentage, second, second_percentage) => {
+            // NOTE: Percentages are normalized to add up to 100%, a smaller sum makes the
+            // result transparent by the missing amount.
+            let (first_percentage, second_percentage) = match (first_percentage, second_percentage)
+            {
+                (None, None) => (0.5, 0.5),
+                (Some(first), None) => (*first, 1.0 - first),
+                (None, Some(second)) => (1.0 - second, *second),
+                (Some(first), Some(second)) => (*first, *second),
+            };
+            let total = first_percentage + second_percentage;
 
-            get_palette_color(color, variables)?
+            let mut color = mix_in(
+                *space,
+                get_palette_color(first, resolver)?,
+                get_palette_color(second, resolver)?,
+                second_percentage / total,
+            );
+
+            if total < 1.0 {
+                color.alpha *= total;
+            }
+
+            color
         }
+        Color::Variable(name) => resolver.variable(name)?,
         Color::Named(color) => Srgba::new(
             color.red as f32 / 255.0,
             color.green as f32 / 255.0,
@@ -120,32 +316,33 @@ fn get_palette_color(
             1.0,
         ),
         Color::Expression(color_type, adjusters) => {
-            let mut current_color = get_palette_color(*color_type, variables)?;
+            let mut current_color = get_palette_color(color_type, resolver)?;
 
             for adjuster in adjusters {
                 match adjuster {
                     Adjuster::Alpha(alpha) => {
-                        current_color = current_color.with_alpha(alpha);
+                        current_color = current_color.with_alpha(*alpha);
                     }
                     Adjuster::Blend(color, percentage, color_space) => {
                         current_color = mix_colors(
                             current_color,
-                            get_palette_color(color, variables)?,
-                            percentage,
-                            color_space,
+                            get_palette_color(color, resolver)?,
+                            *percentage,
+                            *color_space,
                             true,
                         );
                     }
                     Adjuster::BlendAlpha(color, percentage, color_space) => {
                         current_color = mix_colors(
                             current_color,
-                            get_palette_color(color, variables)?,
-                            percentage,
-                            color_space,
+                            get_palette_color(color, resolver)?,
+                            *percentage,
+                            *color_space,
                             false,
                         );
                     }
                     Adjuster::Lightness(lightness, relative) => {
+                        let (lightness, relative) = (*lightness, *relative);
                         let mut hsl: Hsla = current_color.into_color();
                         let negative = lightness < 0.0;
                         if !relative {
@@ -162,6 +359,7 @@ fn get_palette_color(
                         }
                     }
                     Adjuster::Saturation(saturation, relative) => {
+                        let (saturation, relative) = (*saturation, *relative);
                         let mut hsl: Hsla = current_color.into_color();
 
                         if !relative {
@@ -181,9 +379,9 @@ fn get_palette_color(
                         let mut foreground: LinSrgba<f32> = current_color.into_linear();
 
                         let background: LinSrgba<f32> =
-                            get_palette_color(background_color, variables)?.into_linear();
+                            get_palette_color(background_color, resolver)?.into_linear();
 
-                        while foreground.relative_contrast(*background) < ratio {
+                        while foreground.relative_contrast(*background) < *ratio {
                             let fg_luma = foreground.relative_luminance().luma;
                             let bg_luma = background.relative_luminance().luma;
 
@@ -229,34 +427,76 @@ fn mix_colors(
     color_space: Option<ColorSpace>,
     preserve_base_alpha: bool,
 ) -> Srgba {
-    let mut base_color = base_color;
-    let base_alpha = base_color.alpha;
+    let mut mixed = mix_in(
+        color_space.unwrap_or(ColorSpace::RGB),
+        base_color,
+        color,
+        percentage,
+    );
 
-    if let Some(color_space) = color_space {
-        match color_space {
-            ColorSpace::RGB => {
-                base_color = base_color.mix(color, percentage);
-            }
-            ColorSpace::HSL => {
-                let hsl_base: Hsl = base_color.into_color();
-                let hsl_color: Hsl = color.into_color();
+    if preserve_base_alpha {
+        mixed.alpha = base_color.alpha;
+    }
 
-                base_color = hsl_base.mix(hsl_color, percentage).into_color();
-            }
-            ColorSpace::HWB => {
-                let hwb_base: Hwb = base_color.into_color();
-                let hwb_color: Hwb = color.into_color();
+    mixed
+}
 
-                base_color = hwb_base.mix(hwb_color, percentage).into_color();
-            }
+/// Interpolates from `from` to `to` in `color_space`, the alpha is interpolated linearly.
+fn mix_in(color_space: ColorSpace, from: Srgba, to: Srgba, amount: f32) -> Srgba {
+    let (from_color, to_color) = (from.color, to.color);
+
+    let color: Srgb = match color_space {
+        ColorSpace::RGB => from_color.mix(to_color, amount),
+        ColorSpace::LinearRGB => {
+            Srgb::from_linear(from_color.into_linear().mix(to_color.into_linear(), amount))
         }
-    } else {
-        base_color = base_color.mix(color, percentage);
-    }
+        ColorSpace::HSL => {
+            let from_hsl: Hsl = from_color.into_color();
+            from_hsl.mix(to_color.into_color(), amount).into_color()
+        }
+        ColorSpace::HWB => {
+            let from_hwb: Hwb = from_color.into_color();
+            from_hwb.mix(to_color.into_color(), amount).into_color()
+        }
+        ColorSpace::Lab => from_lab(to_lab(from_color).mix(to_lab(to_color), amount)),
+        ColorSpace::Lch => {
+            let from_lch: Lch<D50> = to_lab(from_color).into_color();
+            let to_lch: Lch<D50> = to_lab(to_color).into_color();
+            from_lab(from_lch.mix(to_lch, amount).into_color())
+        }
+        ColorSpace::Oklab => {
+            let from_oklab: Oklab = from_color.into_color();
+            from_oklab.mix(to_color.into_color(), amount).into_color()
+        }
+        ColorSpace::Oklch => {
+            let from_oklch: Oklch = from_color.into_color();
+            from_oklch.mix(to_color.into_color(), amount).into_color()
+        }
+    };
 
-    if preserve_base_alpha {
-        base_color = base_color.with_alpha(base_alpha);
-    }
+    color.with_alpha(from.alpha + (to.alpha - from.alpha) * amount)
+}
+
+/// Converts a D50 [Lab] color, the white point CSS uses for `lab()` and `lch()`, to sRGB.
+fn from_lab(color: Lab<D50>) -> Srgb {
+    let xyz: Xyz<D50> = color.into_color();
+    let [x, y, z] = adapt([xyz.x, xyz.y, xyz.z], &D50_TO_D65);
+
+    Xyz::<D65>::new(x, y, z).into_color()
+}
+
+fn to_lab(color: Srgb) -> Lab<D50> {
+    let xyz: Xyz<D65> = color.into_color();
+    let [x, y, z] = adapt([xyz.x, xyz.y, xyz.z], &D65_TO_D50);
+
+    Xyz::<D50>::new(x, y, z).into_color()
+}
 
-    base_color
+fn adapt(xyz: [f32; 3], matrix: &[[f64; 3]; 3]) -> [f32; 3] {
+    matrix.map(|row| {
+        row.iter()
+            .zip(xyz)
+            .map(|(factor, value)| factor * value as f64)
+            .sum::<f64>() as f32
+    })
 }
@@ -1,3 +1,6 @@
+use std::{collections::HashMap, fmt, path::PathBuf};
+
+use jsonc_parser::{ast::Value, common::Ranged};
 use syntect::{highlighting::ParseThemeError, parsing::ParseScopeError};
 use thiserror::Error;
 
@@ -30,8 +33,15 @@ pub enum ParseError {
     ParseFunction,
     #[error("Failed to parse expression")]
     ParseExpression,
-    #[error("Could not find variable")]
-    UnknownVariable,
+    #[error("Could not find variable \"{0}\"")]
+    UnknownVariable(String),
+    #[error("Failed to parse variable \"{name}\"")]
+    Variable {
+        name: String,
+        source: Box<ParseError>,
+    },
+    #[error("Variables reference each other: {}", .0.join(" → "))]
+    VariableCycle(Vec<String>),
 
     // Number Parsing Errors
     #[error("Failed to parse number string: {0}")]
@@ -46,4 +56,179 @@ pub enum ParseError {
     ParseScope(#[from] ParseScopeError),
     #[error("Failed to parse theme")]
     ParseTheme(#[from] ParseThemeError),
+    #[error("Unknown font style \"{0}\"")]
+    InvalidFontStyle(String),
+
+    // Context
+    #[error("Invalid value \"{input}\" at {path}{}", format_location(.location))]
+    InvalidValue {
+        /// Path of the value in the color scheme, e.g. `rules[12].foreground`
+        path: String,
+        input: String,
+        location: Option<Location>,
+        source: Box<ParseError>,
+    },
+    #[error("Failed to merge {}", .path.display())]
+    Override {
+        path: PathBuf,
+        source: Box<ParseError>,
+    },
+}
+
+fn format_location(location: &Option<Location>) -> String {
+    location
+        .map(|location| format!(" ({location})"))
+        .unwrap_or_default()
+}
+
+/// Position of an error in the parsed text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column number
+    pub column: usize,
+}
+
+impl Location {
+    /// Returns the location of the byte `offset` in `text`.
+    pub fn at(text: &str, offset: usize) -> Location {
+        let before = &text[..offset.min(text.len())];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+
+        Location {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl Location {
+    /// Returns where a JSON error occurred, if it is known.
+    pub fn of_json_error(err: &serde_json::Error) -> Option<Location> {
+        // NOTE: Errors from deserializing an already parsed value have no position.
+        (err.line() > 0).then(|| Location {
+            line: err.line(),
+            column: err.column(),
+        })
+    }
+}
+
+impl From<&jsonc_parser::errors::ParseError> for Location {
+    fn from(err: &jsonc_parser::errors::ParseError) -> Self {
+        Location {
+            line: err.line_display(),
+            column: err.column_display(),
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Collects the location of every value in a JSON document by its path, e.g. `rules[12].scope`.
+pub(crate) fn value_locations(text: &str, value: &Value) -> HashMap<String, Location> {
+    fn collect(text: &str, value: &Value, path: String, locations: &mut HashMap<String, Location>) {
+        match value {
+            Value::Object(object) => {
+                for property in &object.properties {
+                    let key = property.name.as_str();
+                    let child = if path.is_empty() {
+                        key.to_string()
+                    } else {
+                        format!("{path}.{key}")
+                    };
+
+                    collect(text, &property.value, child, locations);
+                }
+            }
+            Value::Array(array) => {
+                for (index, element) in array.elements.iter().enumerate() {
+                    collect(text, element, format!("{path}[{index}]"), locations);
+                }
+            }
+            _ => {}
+        }
+
+        locations.insert(path, Location::at(text, value.range().start));
+    }
+
+    let mut locations = HashMap::new();
+    collect(text, value, String::new(), &mut locations);
+    locations
+}
+
+impl ParseError {
+    /// Returns where the error occurred in the parsed text, if it is known.
+    pub fn location(&self) -> Option<Location> {
+        match self {
+            ParseError::Jsonc(err) => Some(Location::from(err)),
+            ParseError::Json(err) => Location::of_json_error(err),
+            ParseError::InvalidValue { location, .. } => *location,
+            _ => None,
+        }
+    }
+
+    /// Renders the error as a diagnostic that quotes the line of `source` where it occurred,
+    /// followed by the errors that caused it.
+    ///
+    /// ```text
+    ///   × Invalid value "#ééé" at globals.background (line 3, column 19)
+    ///   ╰─▶ Failed to parse hex color
+    ///    ╭─[theme.sublime-color-scheme:3:19]
+    ///  3 │     "background": "#ééé",
+    ///    ·                   ──────
+    ///    ╰────
+    /// ```
+    pub fn render(&self, source: &str, file_name: &str) -> String {
+        let mut output = format!("  × {self}\n");
+        let causes: Vec<String> =
+            std::iter::successors(std::error::Error::source(self), |err| err.source())
+                .map(ToString::to_string)
+                .collect();
+
+        for (index, cause) in causes.iter().enumerate() {
+            let branch = if index + 1 == causes.len() {
+                "╰─▶"
+            } else {
+                "├─▶"
+            };
+            output.push_str(&format!("  {branch} {cause}\n"));
+        }
+
+        let Some(location) = self.location() else {
+            return output;
+        };
+
+        let Some(line) = source.lines().nth(location.line.saturating_sub(1)) else {
+            return output;
+        };
+
+        // NOTE: Values are underlined including their quotes, other errors point at a character.
+        let width = match self {
+            ParseError::InvalidValue { input, .. } => input.chars().count() + 2,
+            _ => 1,
+        };
+
+        let number = location.line.to_string();
+        let gutter = " ".repeat(number.len());
+        let indent: String = line
+            .chars()
+            .take(location.column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        output.push_str(&format!(
+            " {gutter}╭─[{file_name}:{}:{}]\n",
+            location.line, location.column
+        ));
+        output.push_str(&format!(" {number} │ {line}\n"));
+        output.push_str(&format!(" {gutter}· {indent}{}\n", "─".repeat(width)));
+        output.push_str(&format!(" {gutter}╰────\n"));
+
+        output
+    }
 }
@@ -0,0 +1,263 @@
+//! Conversion from a [Theme] back to a [ColorScheme].
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Serialize, Serializer};
+use syntect::highlighting::{
+    Color as SyntectColor, FontStyle, ScopeSelectors, Theme, ThemeSettings, UnderlineOption,
+};
+
+use crate::{error::ParseError, ColorScheme, Rule, RuleColor};
+
+/// Prefix of the variables created by [ColorScheme::factor_variables].
+const VARIABLE_PREFIX: &str = "color";
+
+impl ColorScheme {
+    /// Creates a color scheme with the same colors, styles and rules as `theme`.
+    ///
+    /// Colors are written as hex colors, use [ColorScheme::factor_variables] to move repeated
+    /// colors into variables.
+    pub fn from_theme(theme: &Theme) -> ColorScheme {
+        let mut globals: HashMap<String, String> = global_colors(&theme.settings)
+            .into_iter()
+            .filter_map(|(key, color)| Some((key.to_string(), format_color(color?))))
+            .collect();
+
+        let options = [
+            (
+                "bracket_contents_options",
+                &theme.settings.bracket_contents_options,
+            ),
+            ("brackets_options", &theme.settings.brackets_options),
+            ("tags_options", &theme.settings.tags_options),
+        ];
+
+        for (key, option) in options {
+            if let Some(option) = option.as_ref().and_then(format_underline_option) {
+                globals.insert(key.to_string(), option.to_string());
+            }
+        }
+
+        for (key, css) in [
+            ("popup_css", &theme.settings.popup_css),
+            ("phantom_css", &theme.settings.phantom_css),
+        ] {
+            if let Some(css) = css {
+                globals.insert(key.to_string(), css.clone());
+            }
+        }
+
+        ColorScheme {
+            name: theme.name.clone(),
+            author: theme.author.clone(),
+            variables: None,
+            globals,
+            rules: theme
+                .scopes
+                .iter()
+                .map(|item| Rule {
+                    name: None,
+                    scope: format_selectors(&item.scope),
+                    font_style: item.style.font_style.map(format_font_style),
+                    foreground: item
+                        .style
+                        .foreground
+                        .map(|color| RuleColor::Single(format_color(color))),
+                    background: item
+                        .style
+                        .background
+                        .map(|color| RuleColor::Single(format_color(color))),
+                    foreground_adjust: None,
+                    selection_foreground: None,
+                })
+                .collect(),
+            locations: HashMap::new(),
+        }
+    }
+
+    /// Moves colors that are used more than once into variables named `color1`, `color2`, ...
+    /// in the order they first appear.
+    ///
+    /// Colors that already reference variables or use adjusters are left untouched.
+    pub fn factor_variables(mut self) -> ColorScheme {
+        let mut globals: Vec<_> = self.globals.iter_mut().collect();
+        globals.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut values: Vec<&mut String> = globals
+            .into_iter()
+            .filter(|(key, _)| !key.ends_with("_css") && !key.ends_with("_options"))
+            .map(|(_, value)| value)
+            .collect();
+
+        for rule in &mut self.rules {
+            for color in [&mut rule.foreground, &mut rule.background]
+                .into_iter()
+                .flatten()
+            {
+                match color {
+                    RuleColor::Single(color) => values.push(color),
+                    RuleColor::Multiple(colors) => values.extend(colors),
+                }
+            }
+
+            values.extend(&mut rule.selection_foreground);
+        }
+
+        let mut counts: Vec<(String, usize)> = Vec::new();
+
+        for value in values.iter().filter(|value| value.starts_with('#')) {
+            match counts
+                .iter_mut()
+                .find(|(color, _)| color.as_str() == value.as_str())
+            {
+                Some((_, count)) => *count += 1,
+                None => counts.push((value.to_string(), 1)),
+            }
+        }
+
+        let names: HashMap<String, String> = counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .enumerate()
+            .map(|(index, (color, _))| (color, format!("{VARIABLE_PREFIX}{}", index + 1)))
+            .collect();
+
+        for value in values {
+            if let Some(name) = names.get(value.as_str()) {
+                *value = format!("var({name})");
+            }
+        }
+
+        let variables = self.variables.get_or_insert_with(HashMap::new);
+        variables.extend(names.into_iter().map(|(color, name)| (name, color)));
+
+        self
+    }
+
+    /// Writes the color scheme as pretty printed JSON, the format of `.sublime-color-scheme`
+    /// files.
+    pub fn to_json(&self) -> Result<String, ParseError> {
+        serde_json::to_string_pretty(self).map_err(ParseError::Json)
+    }
+}
+
+/// Serializes a map with its keys in order, so written color schemes don't change between runs.
+pub(crate) fn serialize_sorted<S, V>(
+    map: &HashMap<String, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    V: Serialize,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+pub(crate) fn serialize_sorted_option<S, V>(
+    map: &Option<HashMap<String, V>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    V: Serialize,
+{
+    map.as_ref()
+        .map(|map| map.iter().collect::<BTreeMap<_, _>>())
+        .serialize(serializer)
+}
+
+fn global_colors(settings: &ThemeSettings) -> [(&'static str, Option<SyntectColor>); 25] {
+    [
+        ("foreground", settings.foreground),
+        ("background", settings.background),
+        ("caret", settings.caret),
+        ("line_highlight", settings.line_highlight),
+        ("misspelling", settings.misspelling),
+        ("minimap_border", settings.minimap_border),
+        ("accent", settings.accent),
+        (
+            "bracket_contents_foreground",
+            settings.bracket_contents_foreground,
+        ),
+        ("brackets_foreground", settings.brackets_foreground),
+        ("brackets_background", settings.brackets_background),
+        ("tags_foreground", settings.tags_foreground),
+        ("highlight", settings.highlight),
+        ("find_highlight", settings.find_highlight),
+        (
+            "find_highlight_foreground",
+            settings.find_highlight_foreground,
+        ),
+        ("gutter", settings.gutter),
+        ("gutter_foreground", settings.gutter_foreground),
+        ("selection", settings.selection),
+        ("selection_foreground", settings.selection_foreground),
+        ("selection_border", settings.selection_border),
+        ("inactive_selection", settings.inactive_selection),
+        (
+            "inactive_selection_foreground",
+            settings.inactive_selection_foreground,
+        ),
+        ("guide", settings.guide),
+        ("active_guide", settings.active_guide),
+        ("stack_guide", settings.stack_guide),
+        ("shadow", settings.shadow),
+    ]
+}
+
+/// Formats a color as `#rrggbb`, or `#rrggbbaa` if it isn't opaque.
+pub fn format_color(color: SyntectColor) -> String {
+    if color.a == 255 {
+        format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+    } else {
+        format!(
+            "#{:02x}{:02x}{:02x}{:02x}",
+            color.r, color.g, color.b, color.a
+        )
+    }
+}
+
+fn format_font_style(font_style: FontStyle) -> String {
+    [
+        (FontStyle::BOLD, "bold"),
+        (FontStyle::ITALIC, "italic"),
+        (FontStyle::UNDERLINE, "underline"),
+    ]
+    .into_iter()
+    .filter(|(style, _)| font_style.contains(*style))
+    .map(|(_, name)| name)
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+fn format_underline_option(option: &UnderlineOption) -> Option<&'static str> {
+    match option {
+        UnderlineOption::None => None,
+        UnderlineOption::Underline => Some("underline"),
+        UnderlineOption::StippledUnderline => Some("stippled_underline"),
+        UnderlineOption::SquigglyUnderline => Some("squiggly_underline"),
+    }
+}
+
+/// Formats scope selectors in the syntax they are parsed from, e.g. `source - comment, string`.
+fn format_selectors(selectors: &ScopeSelectors) -> String {
+    let format_path = |path: &syntect::parsing::ScopeStack| {
+        path.as_slice()
+            .iter()
+            .map(|scope| scope.build_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    selectors
+        .selectors
+        .iter()
+        .map(|selector| {
+            std::iter::once(format_path(&selector.path))
+                .chain(selector.excludes.iter().map(format_path))
+                .collect::<Vec<_>>()
+                .join(" - ")
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
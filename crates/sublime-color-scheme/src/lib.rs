@@ -1,51 +1,228 @@
-use color::get_color;
+use color::VariableResolver;
 use log::debug;
-use serde::Deserialize;
-use std::{collections::HashMap, path::Path, str::FromStr};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 use syntect::highlighting::{
     FontStyle, ScopeSelectors, StyleModifier, Theme, ThemeItem, ThemeSettings, UnderlineOption,
 };
 
+pub mod ast;
 pub mod color;
 pub mod error;
+mod export;
+mod merge;
 pub mod parser;
+pub mod rule;
 
-use crate::error::ParseError;
+use crate::error::{value_locations, Location, ParseError};
+pub use crate::export::format_color;
+use crate::export::{serialize_sorted, serialize_sorted_option};
+pub use crate::rule::{Rule, RuleColor, RuleFontStyle, StyleRule};
 
-#[derive(Deserialize, Debug)]
-pub struct Rule {
-    pub name: Option<String>,
-    pub scope: String,
-    pub font_style: Option<String>,
-    pub foreground: Option<String>,
-    pub background: Option<String>,
-}
-
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct ColorScheme {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub author: Option<String>,
+    #[serde(
+        serialize_with = "serialize_sorted_option",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub variables: Option<HashMap<String, String>>,
+    #[serde(default, serialize_with = "serialize_sorted")]
     pub globals: HashMap<String, String>,
+    #[serde(default)]
     pub rules: Vec<Rule>,
+    /// Locations of the values in the parsed text by their path, e.g. `rules[12].foreground`
+    #[serde(skip)]
+    pub(crate) locations: HashMap<String, Location>,
 }
 
 impl FromStr for ColorScheme {
     type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         // NOTE: Need to remove comments and remove leading commas since JSON doesn't support them.
-        let value: serde_json::Value = jsonc_parser::parse_to_ast(
+        let ast = jsonc_parser::parse_to_ast(
             s,
             &jsonc_parser::CollectOptions {
                 comments: jsonc_parser::CommentCollectionStrategy::Off,
                 tokens: false,
             },
             &jsonc_parser::ParseOptions::default(),
-        )?
-        .value
-        .into();
+        )?;
+
+        let locations = ast
+            .value
+            .as_ref()
+            .map(|value| value_locations(s, value))
+            .unwrap_or_default();
+        let value: serde_json::Value = ast.value.into();
 
-        serde_json::from_value(value).map_err(ParseError::Json)
+        let mut scheme: ColorScheme = serde_json::from_value(value).map_err(ParseError::Json)?;
+        scheme.locations = locations;
+
+        Ok(scheme)
+    }
+}
+
+/// Globals that are converted to colors of the [ThemeSettings].
+pub(crate) const GLOBAL_COLORS: &[&str] = &[
+    "foreground",
+    "background",
+    "caret",
+    "line_highlight",
+    "misspelling",
+    "minimap_border",
+    "accent",
+    "bracket_contents_foreground",
+    "brackets_foreground",
+    "brackets_background",
+    "tags_foreground",
+    "highlight",
+    "find_highlight",
+    "find_highlight_foreground",
+    "gutter",
+    "gutter_foreground",
+    "selection",
+    "selection_foreground",
+    "selection_border",
+    "inactive_selection",
+    "inactive_selection_foreground",
+    "guide",
+    "active_guide",
+    "stack_guide",
+    "shadow",
+];
+
+impl ColorScheme {
+    /// Describes the globals that are dropped when converting to a [Theme] because their value
+    /// fails to parse.
+    pub fn warnings(&self) -> Vec<String> {
+        let variables = self.variables.clone().unwrap_or_default();
+        let resolver = VariableResolver::new(&variables);
+
+        let mut warnings: Vec<String> = self
+            .globals
+            .iter()
+            .filter_map(|(key, value)| {
+                let result = match &key[..] {
+                    "bracket_contents_options" | "brackets_options" | "tags_options" => {
+                        resolver.text(value).and_then(|value| {
+                            UnderlineOption::from_str(&value)
+                                .map(|_| ())
+                                .map_err(ParseError::from)
+                        })
+                    }
+                    key if GLOBAL_COLORS.contains(&key) => resolver.color(value).map(|_| ()),
+                    _ => Ok(()),
+                };
+
+                let at = self
+                    .locations
+                    .get(&format!("globals.{key}"))
+                    .map(|location| format!(" at {location}"))
+                    .unwrap_or_default();
+
+                result
+                    .err()
+                    .map(|err| format!("Ignoring global \"{key}\" = \"{value}\"{at}: {err}"))
+            })
+            .collect();
+
+        warnings.extend(self.rules.iter().filter_map(|rule| {
+            let features = rule.unsupported_features();
+
+            (!features.is_empty()).then(|| {
+                format!(
+                    "Rule \"{}\" uses {}, which syntect doesn't support",
+                    rule.name.as_deref().unwrap_or(&rule.scope),
+                    features.join(", ")
+                )
+            })
+        }));
+
+        warnings.sort();
+        warnings
+    }
+
+    /// Resolves every rule, keeping the features a [Theme] can't represent such as hashed
+    /// foregrounds and background gradients.
+    pub fn style_rules(&self) -> Result<Vec<StyleRule>, ParseError> {
+        let variables = self.variables.clone().unwrap_or_default();
+        let resolver = VariableResolver::new(&variables);
+
+        self.rules
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| {
+                rule.resolve_with(&resolver, &|property, input, err| {
+                    self.value_error(format!("rules[{index}].{property}"), input, err)
+                })
+            })
+            .collect()
+    }
+
+    /// Adds the path, location and input of a value to an error about it.
+    pub(crate) fn value_error(&self, path: String, input: &str, err: ParseError) -> ParseError {
+        ParseError::InvalidValue {
+            location: self.locations.get(&path).copied(),
+            path,
+            input: input.to_string(),
+            source: Box::new(err),
+        }
+    }
+
+    /// Converts the rule at `index` to a [ThemeItem].
+    ///
+    /// Hashed foregrounds and background gradients fall back to their first color.
+    fn theme_item(
+        &self,
+        index: usize,
+        rule: &Rule,
+        resolver: &VariableResolver,
+    ) -> Result<ThemeItem, ParseError> {
+        let path = |property: &str| format!("rules[{index}].{property}");
+
+        let first_color = |property: &str, color: &Option<RuleColor>| {
+            let (path, input) = match color {
+                None => return Ok(None),
+                Some(RuleColor::Single(color)) => (path(property), color.as_str()),
+                Some(RuleColor::Multiple(colors)) => match colors.first() {
+                    Some(color) => (format!("{}[0]", path(property)), color.as_str()),
+                    None => return Ok(None),
+                },
+            };
+
+            resolver
+                .color(input)
+                .map(Some)
+                .map_err(|err| self.value_error(path, input, err))
+        };
+
+        Ok(ThemeItem {
+            scope: ScopeSelectors::from_str(&rule.scope)
+                .map_err(|err| self.value_error(path("scope"), &rule.scope, err.into()))?,
+            style: StyleModifier {
+                foreground: first_color("foreground", &rule.foreground)?,
+                background: first_color("background", &rule.background)?,
+                font_style: rule
+                    .font_style
+                    .as_deref()
+                    .map(|input| {
+                        resolver
+                            .text(input)
+                            .and_then(|style| RuleFontStyle::from_str(&style))
+                            .map(FontStyle::from)
+                            .map_err(|err| self.value_error(path("font_style"), input, err))
+                    })
+                    .transpose()?,
+            },
+        })
     }
 }
 
@@ -53,92 +230,74 @@ impl TryFrom<ColorScheme> for Theme {
     type Error = ParseError;
     fn try_from(value: ColorScheme) -> Result<Self, Self::Error> {
         let mut settings = ThemeSettings::default();
-        let variables = value.variables.unwrap_or_default();
+        let variables = value.variables.clone().unwrap_or_default();
+        let resolver = VariableResolver::new(&variables);
+        let underline_option = |value: &str| {
+            resolver
+                .text(value)
+                .ok()
+                .and_then(|value| UnderlineOption::from_str(&value).ok())
+        };
 
         for (key, value) in &value.globals {
             debug!("Got global: {} = {}", key, value);
             match &key[..] {
-                "foreground" => settings.foreground = get_color(value, &variables).ok(),
-                "background" => settings.background = get_color(value, &variables).ok(),
-                "caret" => settings.caret = get_color(value, &variables).ok(),
-                "line_highlight" => settings.line_highlight = get_color(value, &variables).ok(),
-                "misspelling" => settings.misspelling = get_color(value, &variables).ok(),
-                "minimap_border" => settings.minimap_border = get_color(value, &variables).ok(),
-                "accent" => settings.accent = get_color(value, &variables).ok(),
+                "foreground" => settings.foreground = resolver.color(value).ok(),
+                "background" => settings.background = resolver.color(value).ok(),
+                "caret" => settings.caret = resolver.color(value).ok(),
+                "line_highlight" => settings.line_highlight = resolver.color(value).ok(),
+                "misspelling" => settings.misspelling = resolver.color(value).ok(),
+                "minimap_border" => settings.minimap_border = resolver.color(value).ok(),
+                "accent" => settings.accent = resolver.color(value).ok(),
                 "popup_css" => settings.popup_css = Some(value.clone()),
                 "phantom_css" => settings.phantom_css = Some(value.clone()),
                 "bracket_contents_foreground" => {
-                    settings.bracket_contents_foreground = get_color(value, &variables).ok()
+                    settings.bracket_contents_foreground = resolver.color(value).ok()
                 }
                 "bracket_contents_options" => {
-                    settings.bracket_contents_options = UnderlineOption::from_str(value).ok()
-                }
-                "brackets_foreground" => {
-                    settings.brackets_foreground = get_color(value, &variables).ok()
-                }
-                "brackets_background" => {
-                    settings.brackets_background = get_color(value, &variables).ok()
+                    settings.bracket_contents_options = underline_option(value)
                 }
-                "brackets_options" => {
-                    settings.brackets_options = UnderlineOption::from_str(value).ok()
-                }
-                "tags_foreground" => settings.tags_foreground = get_color(value, &variables).ok(),
-                "tags_options" => settings.tags_options = UnderlineOption::from_str(value).ok(),
-                "highlight" => settings.highlight = get_color(value, &variables).ok(),
-                "find_highlight" => settings.find_highlight = get_color(value, &variables).ok(),
+                "brackets_foreground" => settings.brackets_foreground = resolver.color(value).ok(),
+                "brackets_background" => settings.brackets_background = resolver.color(value).ok(),
+                "brackets_options" => settings.brackets_options = underline_option(value),
+                "tags_foreground" => settings.tags_foreground = resolver.color(value).ok(),
+                "tags_options" => settings.tags_options = underline_option(value),
+                "highlight" => settings.highlight = resolver.color(value).ok(),
+                "find_highlight" => settings.find_highlight = resolver.color(value).ok(),
                 "find_highlight_foreground" => {
-                    settings.find_highlight_foreground = get_color(value, &variables).ok()
-                }
-                "gutter" => settings.gutter = get_color(value, &variables).ok(),
-                "gutter_foreground" => {
-                    settings.gutter_foreground = get_color(value, &variables).ok()
+                    settings.find_highlight_foreground = resolver.color(value).ok()
                 }
-                "selection" => settings.selection = get_color(value, &variables).ok(),
+                "gutter" => settings.gutter = resolver.color(value).ok(),
+                "gutter_foreground" => settings.gutter_foreground = resolver.color(value).ok(),
+                "selection" => settings.selection = resolver.color(value).ok(),
                 "selection_foreground" => {
-                    settings.selection_foreground = get_color(value, &variables).ok()
-                }
-                "selection_border" => settings.selection_border = get_color(value, &variables).ok(),
-                "inactive_selection" => {
-                    settings.inactive_selection = get_color(value, &variables).ok()
+                    settings.selection_foreground = resolver.color(value).ok()
                 }
+                "selection_border" => settings.selection_border = resolver.color(value).ok(),
+                "inactive_selection" => settings.inactive_selection = resolver.color(value).ok(),
                 "inactive_selection_foreground" => {
-                    settings.inactive_selection_foreground = get_color(value, &variables).ok()
+                    settings.inactive_selection_foreground = resolver.color(value).ok()
                 }
-                "guide" => settings.guide = get_color(value, &variables).ok(),
-                "active_guide" => settings.active_guide = get_color(value, &variables).ok(),
-                "stack_guide" => settings.stack_guide = get_color(value, &variables).ok(),
-                "shadow" => settings.shadow = get_color(value, &variables).ok(),
+                "guide" => settings.guide = resolver.color(value).ok(),
+                "active_guide" => settings.active_guide = resolver.color(value).ok(),
+                "stack_guide" => settings.stack_guide = resolver.color(value).ok(),
+                "shadow" => settings.shadow = resolver.color(value).ok(),
                 _ => (), // E.g. "shadowWidth" and "invisibles" are ignored
             }
         }
 
+        let scopes = value
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| value.theme_item(index, rule, &resolver))
+            .collect::<Result<Vec<_>, ParseError>>()?;
+
         Ok(Self {
             name: value.name,
             author: value.author,
             settings,
-            scopes: value
-                .rules
-                .into_iter()
-                .map(|rule| {
-                    Ok(ThemeItem {
-                        scope: ScopeSelectors::from_str(&rule.scope)?,
-                        style: StyleModifier {
-                            foreground: rule
-                                .foreground
-                                .map(|s| get_color(&s, &variables))
-                                .transpose()?,
-                            background: rule
-                                .background
-                                .map(|s| get_color(&s, &variables))
-                                .transpose()?,
-                            font_style: rule
-                                .font_style
-                                .map(|s| FontStyle::from_str(&s))
-                                .transpose()?,
-                        },
-                    })
-                })
-                .collect::<Result<Vec<_>, ParseError>>()?,
+            scopes,
         })
     }
 }
@@ -158,6 +317,22 @@ pub fn parse_color_scheme_file(path: &Path) -> Result<ColorScheme, ParseError> {
     parse_color_scheme(&scheme)
 }
 
+/// Parse a color scheme from a file and merge the files in `overrides` into it, in order.
+///
+/// See [ColorScheme::merge] for how overrides are applied.
+pub fn parse_color_scheme_with_overrides(
+    path: &Path,
+    overrides: &[PathBuf],
+) -> Result<ColorScheme, ParseError> {
+    let mut scheme = parse_color_scheme_file(path)?;
+
+    for path in overrides {
+        scheme.merge_file(path)?;
+    }
+
+    Ok(scheme)
+}
+
 #[cfg(test)]
 mod tests {
     use log::debug;
@@ -184,6 +359,419 @@ mod tests {
         }
     }
 
+    #[test]
+    fn reject_malformed_colors() {
+        start_log();
+
+        let scheme = ColorScheme::from_str(
+            r##"{
+                "variables": { "loop": "var(loop)" },
+                "globals": { "background": "#ééé", "foreground": "var(loop)" },
+                "rules": [{ "scope": "comment", "foreground": "hwb(120, 50%, 20%)" }]
+            }"##,
+        )
+        .expect("Failed to parse theme");
+
+        let warnings = scheme.warnings();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("background"));
+        assert!(warnings[1].contains("loop → loop"));
+
+        let theme = Theme::try_from(scheme).expect("Failed to convert to theme");
+        assert!(theme.settings.background.is_none());
+        assert!(theme.scopes[0].style.foreground.is_some());
+    }
+
+    #[test]
+    fn keep_full_rule_model() {
+        start_log();
+
+        let scheme = ColorScheme::from_str(
+            r##"{
+                "globals": {},
+                "rules": [
+                    {
+                        "scope": "source - punctuation - keyword",
+                        "foreground": ["#ff0000", "#0000ff"],
+                        "background": ["#000", "#111"],
+                        "selection_foreground": "#fff",
+                        "font_style": "bold glow squiggly_underline"
+                    },
+                    { "scope": "comment", "font_style": "sparkly" }
+                ]
+            }"##,
+        )
+        .expect("Failed to parse theme");
+
+        let warnings = scheme.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("hashed foreground"));
+
+        assert!(matches!(
+            scheme.rules[1].font_style(),
+            Err(ParseError::InvalidFontStyle(style)) if style == "sparkly"
+        ));
+
+        let rule = scheme.rules[0].resolve(&HashMap::new()).unwrap();
+        let font_style = rule.font_style.unwrap();
+        assert!(font_style.bold && font_style.glow && font_style.squiggly_underline);
+        assert_eq!(rule.background.len(), 2);
+        assert!(rule.selection_foreground.is_some());
+
+        let color = rule.foreground_for("main").unwrap();
+        assert_eq!(rule.foreground_for("main"), Some(color));
+        assert_eq!(color.g, 0);
+    }
+
+    #[test]
+    fn round_trip_schemes() {
+        start_log();
+
+        let schemes = vec![
+            include_str!("../assets/schemes/ayu-dark.sublime-color-scheme"),
+            include_str!("../assets/schemes/ayu-mirage.sublime-color-scheme"),
+            include_str!("../assets/schemes/ayu-light.sublime-color-scheme"),
+            include_str!("../assets/schemes/Catppuccin Latte.sublime-color-scheme"),
+            include_str!("../assets/schemes/Catppuccin Mocha.sublime-color-scheme"),
+            include_str!("../assets/schemes/Catppuccin Frappe.sublime-color-scheme"),
+            include_str!("../assets/schemes/Catppuccin Macchiato.sublime-color-scheme"),
+            include_str!("../assets/schemes/Gruvbox Material Dark.sublime-color-scheme"),
+            include_str!("../assets/schemes/Kanagawa.sublime-color-scheme"),
+            include_str!("../assets/schemes/Nord.sublime-color-scheme"),
+            include_str!("../assets/schemes/Monokai.sublime-color-scheme"),
+        ];
+
+        for scheme in schemes {
+            let theme = Theme::try_from(ColorScheme::from_str(scheme).unwrap()).unwrap();
+
+            for factor_variables in [false, true] {
+                let mut written = ColorScheme::from_theme(&theme);
+
+                if factor_variables {
+                    written = written.factor_variables();
+                }
+
+                let json = written.to_json().unwrap();
+                let parsed = Theme::try_from(ColorScheme::from_str(&json).unwrap()).unwrap();
+
+                assert_eq!(format!("{parsed:?}"), format!("{theme:?}"));
+            }
+        }
+    }
+
+    #[test]
+    fn factor_repeated_colors() {
+        let scheme = ColorScheme::from_str(
+            r##"{
+                "globals": { "foreground": "#eeeeee", "caret": "#eeeeee", "background": "#000" },
+                "rules": [
+                    { "scope": "comment", "foreground": "#eeeeee", "background": "#123456" },
+                    { "scope": "string", "foreground": "#123456" }
+                ]
+            }"##,
+        )
+        .unwrap()
+        .factor_variables();
+
+        let variables = scheme.variables.unwrap();
+        assert_eq!(variables.len(), 2);
+        assert_eq!(variables["color1"], "#eeeeee");
+        assert_eq!(variables["color2"], "#123456");
+        assert_eq!(scheme.globals["caret"], "var(color1)");
+        assert_eq!(scheme.globals["background"], "#000");
+        assert_eq!(
+            scheme.rules[1].foreground,
+            Some(RuleColor::Single("var(color2)".to_string()))
+        );
+    }
+
+    #[test]
+    fn track_variable_dependents() {
+        use crate::ast::{ColorLocation, RuleProperty};
+        use crate::parser::parse_color;
+
+        let scheme = ColorScheme::from_str(
+            r##"{
+                "variables": { "blue": "#0000ff", "accent": "color(var(blue) alpha(0.5))" },
+                "globals": { "foreground": "var(blue)", "background": "#000" },
+                "rules": [{ "scope": "comment", "foreground": ["#fff", "var(accent)"] }]
+            }"##,
+        )
+        .expect("Failed to parse theme");
+
+        let mut ast = scheme.ast().unwrap();
+
+        assert_eq!(
+            ast.dependents("blue"),
+            vec![
+                ColorLocation::Variable("accent".to_string()),
+                ColorLocation::Global("foreground".to_string()),
+                ColorLocation::Rule {
+                    index: 0,
+                    property: RuleProperty::Foreground(1)
+                },
+            ]
+        );
+        assert_eq!(ast.dependents("accent").len(), 1);
+
+        ast.variables
+            .insert("blue".to_string(), parse_color("#ff0000").unwrap());
+
+        let foreground = ColorLocation::Global("foreground".to_string());
+        let color = ast.evaluate_at(&foreground).unwrap().unwrap();
+        assert_eq!((color.r, color.b), (255, 0));
+
+        let location = ColorLocation::Rule {
+            index: 0,
+            property: RuleProperty::Foreground(1),
+        };
+        assert_eq!(ast.get(&location).unwrap().to_string(), "var(accent)");
+        assert_eq!(ast.evaluate_at(&location).unwrap().unwrap().a, 128);
+    }
+
+    #[test]
+    fn evaluate_css_color_level_4() {
+        start_log();
+
+        // NOTE: Expected values follow the sample conversions of the CSS Color Level 4
+        // specification, off by one is allowed for rounding.
+        let colors = [
+            ("rgb(255 0 0 / 50%)", [255, 0, 0, 128]),
+            ("rgb(100% 50% 0%)", [255, 128, 0, 255]),
+            ("hsl(0.5turn 100% 50%)", [0, 255, 255, 255]),
+            ("hwb(200grad 20% 30%)", [51, 179, 179, 255]),
+            ("lab(50 0 0)", [119, 119, 119, 255]),
+            ("lab(52.2345 40.1645 59.9971)", [198, 93, 6, 255]),
+            ("lch(29.2345 44.2 27)", [125, 35, 41, 255]),
+            ("oklab(0.628 0.225 0.126)", [255, 0, 0, 255]),
+            ("oklch(70% 0.1 200 / 0.5)", [64, 177, 183, 128]),
+            ("color-mix(in srgb, #fff 25%, black)", [64, 64, 64, 255]),
+            ("color-mix(in srgb, red 30%, blue 30%)", [128, 0, 128, 153]),
+            ("color-mix(in srgb-linear, red, blue)", [188, 0, 188, 255]),
+            ("color-mix(in lab, white, black)", [119, 119, 119, 255]),
+            ("color-mix(in oklab, white, black)", [99, 99, 99, 255]),
+            ("color-mix(in lch, red, blue)", [245, 0, 134, 255]),
+            ("color-mix(in oklch, red, blue)", [186, 0, 194, 255]),
+        ];
+
+        let variables = HashMap::new();
+
+        for (input, expected) in colors {
+            let color = color::get_color(input, &variables)
+                .unwrap_or_else(|err| panic!("Failed to evaluate {input}: {err}"));
+            let actual = [color.r, color.g, color.b, color.a];
+
+            assert!(
+                actual
+                    .iter()
+                    .zip(expected)
+                    .all(|(actual, expected)| actual.abs_diff(expected) <= 1),
+                "{input} evaluated to {actual:?}, expected {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn merge_overrides() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+
+        let base = dir.join("Base.sublime-color-scheme");
+        std::fs::write(
+            &base,
+            r##"{
+                "name": "Base",
+                "variables": { "accent": "#ff0000", "bg": "#000000" },
+                "globals": { "background": "var(bg)", "caret": "var(accent)" },
+                "rules": [{ "scope": "keyword", "foreground": "var(accent)" }]
+            }"##,
+        )
+        .unwrap();
+
+        let user = dir.join("User.sublime-color-scheme");
+        std::fs::write(
+            &user,
+            r##"{
+                "variables": { "accent": "#00ff00" },
+                "globals": { "foreground": "#ffffff" },
+                "rules": [{ "scope": "comment", "font_style": "italic" }]
+            }"##,
+        )
+        .unwrap();
+
+        let broken = dir.join("Broken.sublime-color-scheme");
+        std::fs::write(&broken, "{ \"rules\": [").unwrap();
+
+        let scheme = parse_color_scheme_with_overrides(&base, &[user.clone()]);
+        let error = parse_color_scheme_with_overrides(&base, &[user, broken.clone()]).unwrap_err();
+
+        let scheme = scheme.expect("Failed to merge overrides");
+        assert_eq!(scheme.name.as_deref(), Some("Base"));
+        assert_eq!(scheme.variables.as_ref().unwrap()["accent"], "#00ff00");
+        assert_eq!(scheme.variables.as_ref().unwrap()["bg"], "#000000");
+        assert_eq!(scheme.rules.len(), 2);
+        assert!(!scheme.locations.contains_key("variables.accent"));
+        assert!(scheme.locations.contains_key("variables.bg"));
+
+        let theme = Theme::try_from(scheme).expect("Failed to convert to theme");
+        let green = Some(syntect::highlighting::Color {
+            r: 0,
+            g: 255,
+            b: 0,
+            a: 255,
+        });
+        assert_eq!(theme.settings.caret, green);
+        assert_eq!(theme.scopes[0].style.foreground, green);
+        assert!(theme.settings.foreground.is_some());
+
+        assert!(matches!(error, ParseError::Override { ref path, .. } if *path == broken));
+    }
+
+    #[test]
+    fn merge_partial_overrides() {
+        let base = r##"{
+            "variables": { "accent": "#ff0000" },
+            "globals": { "caret": "var(accent)" },
+            "rules": [{ "scope": "keyword", "foreground": "var(accent)" }]
+        }"##;
+
+        let mut scheme = parse_color_scheme(base).unwrap();
+        let variables = parse_color_scheme(r##"{ "variables": { "accent": "#00ff00" } }"##)
+            .expect("Failed to parse override with only variables");
+        scheme.merge(variables);
+        assert_eq!(scheme.variables.as_ref().unwrap()["accent"], "#00ff00");
+        assert_eq!(scheme.globals["caret"], "var(accent)");
+        assert_eq!(scheme.rules.len(), 1);
+
+        let mut scheme = parse_color_scheme(base).unwrap();
+        let rules =
+            parse_color_scheme(r#"{ "rules": [{ "scope": "comment", "font_style": "italic" }] }"#)
+                .expect("Failed to parse override with only rules");
+        scheme.merge(rules);
+        assert_eq!(scheme.variables.as_ref().unwrap()["accent"], "#ff0000");
+        assert_eq!(scheme.globals.len(), 1);
+        assert_eq!(scheme.rules.len(), 2);
+    }
+
+    #[test]
+    fn locate_invalid_values() {
+        let source = r##"{
+  "variables": { "blue": "#00f" },
+  "globals": {},
+  "rules": [
+    { "scope": "comment", "foreground": "var(blue)" },
+    { "scope": "string", "foreground": "color(var(red) alpha(0.5))" }
+  ]
+}"##;
+
+        let scheme = ColorScheme::from_str(source).expect("Failed to parse theme");
+        let error = Theme::try_from(scheme).unwrap_err();
+
+        let ParseError::InvalidValue {
+            path,
+            input,
+            source: cause,
+            ..
+        } = &error
+        else {
+            panic!("Expected an invalid value, got {error:?}");
+        };
+
+        assert_eq!(path, "rules[1].foreground");
+        assert_eq!(input, "color(var(red) alpha(0.5))");
+        assert!(matches!(**cause, ParseError::UnknownVariable(ref name) if name == "red"));
+        assert_eq!(
+            error.location(),
+            Some(error::Location {
+                line: 6,
+                column: 40
+            })
+        );
+
+        let rendered = error.render(source, "broken.sublime-color-scheme");
+        assert!(rendered.contains("╰─▶ Could not find variable \"red\""));
+        assert!(rendered.contains("[broken.sublime-color-scheme:6:40]"));
+        assert!(rendered.contains(r#" 6 │     { "scope": "string", "foreground": "color"#));
+    }
+
+    #[test]
+    fn locate_syntax_errors() {
+        let error = ColorScheme::from_str("{\n  \"name\": tru\n}").unwrap_err();
+
+        assert_eq!(error.location().map(|location| location.line), Some(2));
+    }
+
+    #[test]
+    fn resolve_variables_once() {
+        use std::cell::Cell;
+
+        use crate::color::{VariableResolver, Variables};
+
+        struct Counting {
+            variables: HashMap<String, String>,
+            lookups: Cell<usize>,
+        }
+
+        impl Variables for Counting {
+            fn variable(&self, name: &str) -> Result<parser::Color, ParseError> {
+                self.lookups.set(self.lookups.get() + 1);
+                self.variables.variable(name)
+            }
+
+            fn text(&self, name: &str) -> Option<String> {
+                self.variables.text(name)
+            }
+        }
+
+        let variables = Counting {
+            variables: HashMap::from([
+                ("a".to_string(), "#336699".to_string()),
+                ("b".to_string(), "color(var(a) l(+ 10%))".to_string()),
+            ]),
+            lookups: Cell::new(0),
+        };
+        let resolver = VariableResolver::new(&variables);
+
+        for _ in 0..3 {
+            resolver.color("var(a)").unwrap();
+            resolver.color("color(var(b) alpha(0.5))").unwrap();
+        }
+        assert_eq!(variables.lookups.get(), 2);
+
+        let cycle = HashMap::from([
+            ("a".to_string(), "var(b)".to_string()),
+            ("b".to_string(), "var(c)".to_string()),
+            ("c".to_string(), "color(var(a) alpha(0.5))".to_string()),
+        ]);
+        let error = VariableResolver::new(&cycle).color("var(a)").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Variables reference each other: a → b → c → a"
+        );
+
+        let scheme = ColorScheme::from_str(
+            r##"{
+                "variables": { "emphasis": "var(style)", "style": "bold italic", "fg": "#fff" },
+                "globals": {},
+                "rules": [{ "scope": "markup", "foreground": "var(fg)", "font_style": "var(emphasis)" }]
+            }"##,
+        )
+        .expect("Failed to parse theme");
+
+        let rules = scheme.style_rules().expect("Failed to resolve rules");
+        let font_style = rules[0].font_style.unwrap();
+        assert!(font_style.bold && font_style.italic);
+
+        let ast = scheme.ast().expect("Failed to parse colors");
+        assert_eq!(ast.values["style"], "bold italic");
+
+        let theme = Theme::try_from(scheme).expect("Failed to convert to theme");
+        assert_eq!(
+            theme.scopes[0].style.font_style,
+            Some(FontStyle::BOLD | FontStyle::ITALIC)
+        );
+    }
+
     #[test]
     fn convert_theme_with_variables() {
         start_log();
@@ -0,0 +1,55 @@
+//! Merging color schemes the way Sublime Text applies user overrides.
+//!
+//! A color scheme in the `User` package with the same name as an installed one extends it
+//! instead of replacing it, which is how users customize schemes without copying them.
+
+use std::{collections::HashMap, path::Path};
+
+use crate::{error::ParseError, parse_color_scheme_file, ColorScheme};
+
+impl ColorScheme {
+    /// Merges `overrides` into the scheme.
+    ///
+    /// Variables and globals replace the ones with the same name, so colors that reference an
+    /// overridden variable change too. Rules are appended and take precedence over the rules of
+    /// the scheme. The name and author are only replaced if the overrides set them.
+    pub fn merge(&mut self, overrides: ColorScheme) {
+        let ColorScheme {
+            name,
+            author,
+            variables,
+            globals,
+            rules,
+            ..
+        } = overrides;
+
+        // NOTE: Locations of the merged values point into the other file, errors in them are
+        // reported without one.
+        for name in variables.iter().flat_map(HashMap::keys) {
+            self.locations.remove(&format!("variables.{name}"));
+        }
+
+        for key in globals.keys() {
+            self.locations.remove(&format!("globals.{key}"));
+        }
+
+        self.name = name.or(self.name.take());
+        self.author = author.or(self.author.take());
+        self.variables
+            .get_or_insert_with(HashMap::new)
+            .extend(variables.unwrap_or_default());
+        self.globals.extend(globals);
+        self.rules.extend(rules);
+    }
+
+    /// Parses the color scheme at `path` and merges it into the scheme, see [ColorScheme::merge].
+    pub fn merge_file(&mut self, path: &Path) -> Result<(), ParseError> {
+        let overrides = parse_color_scheme_file(path).map_err(|err| ParseError::Override {
+            path: path.to_path_buf(),
+            source: Box::new(err),
+        })?;
+
+        self.merge(overrides);
+        Ok(())
+    }
+}
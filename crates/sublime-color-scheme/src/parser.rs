@@ -1,10 +1,13 @@
 use crate::error::ParseError;
 use log::{debug, error, info};
 use palette::{encoding::Srgb, named, rgb::Rgb};
-use std::str::FromStr;
+use std::{f32::consts::PI, fmt, str::FromStr};
 
 /// A color in a color scheme
-#[derive(Debug, PartialEq)]
+///
+/// Colors written in the space separated syntax of CSS Color Level 4, e.g. `rgb(255 0 0 / 50%)`,
+/// are parsed into the same variants as the legacy syntax, hues are rounded to whole degrees.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Color {
     Hex(String),
     HexAlpha(String),
@@ -13,9 +16,19 @@ pub enum Color {
     HSL(u32, f32, f32),
     HSLA(u32, f32, f32, f32),
     HWB(u32, f32, f32, Option<f32>),
+    /// CIE Lab with the D50 white point `(Lightness, A, B, Alpha)`, lightness from 0 to 100
+    Lab(f32, f32, f32, Option<f32>),
+    /// CIE LCh with the D50 white point `(Lightness, Chroma, Hue, Alpha)`
+    Lch(f32, f32, f32, Option<f32>),
+    /// Oklab `(Lightness, A, B, Alpha)`, lightness from 0 to 1
+    Oklab(f32, f32, f32, Option<f32>),
+    /// Oklch `(Lightness, Chroma, Hue, Alpha)`
+    Oklch(f32, f32, f32, Option<f32>),
     Named(Rgb<Srgb, u8>),
     Variable(String),
     Expression(Box<Color>, Vec<Adjuster>),
+    /// Two colors mixed with `color-mix()` `(ColorSpace, Color, Percentage, Color, Percentage)`
+    Mix(ColorSpace, Box<Color>, Option<f32>, Box<Color>, Option<f32>),
 }
 
 #[derive(Debug, PartialEq)]
@@ -25,6 +38,11 @@ pub enum ColorFunction {
     HSL,
     HSLA,
     HWB,
+    Lab,
+    Lch,
+    Oklab,
+    Oklch,
+    Mix,
     Variable,
     Expression,
 }
@@ -40,6 +58,11 @@ impl FromStr for ColorFunction {
             "hsl" => Self::HSL,
             "hsla" => Self::HSLA,
             "hwb" => Self::HWB,
+            "lab" => Self::Lab,
+            "lch" => Self::Lch,
+            "oklab" => Self::Oklab,
+            "oklch" => Self::Oklch,
+            "color-mix" => Self::Mix,
             "var" => Self::Variable,
             "color" => Self::Expression,
             _ => return Err(ParseError::ParseFunction),
@@ -49,7 +72,7 @@ impl FromStr for ColorFunction {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Adjuster {
     /// Blend two colors `(Color, Percentage, ColorSpace)`
     Blend(Color, f32, Option<ColorSpace>),
@@ -82,11 +105,16 @@ pub enum AdjusterKind {
     MinContrast,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ColorSpace {
     RGB,
+    LinearRGB,
     HSL,
     HWB,
+    Lab,
+    Lch,
+    Oklab,
+    Oklch,
 }
 
 impl FromStr for ColorSpace {
@@ -94,14 +122,220 @@ impl FromStr for ColorSpace {
 
     fn from_str(value: &str) -> Result<Self, Self::Err> {
         match value.to_lowercase().as_str() {
-            "rgb" => Ok(ColorSpace::RGB),
+            "rgb" | "srgb" => Ok(ColorSpace::RGB),
+            "srgb-linear" => Ok(ColorSpace::LinearRGB),
             "hsl" => Ok(ColorSpace::HSL),
             "hwb" => Ok(ColorSpace::HWB),
+            "lab" => Ok(ColorSpace::Lab),
+            "lch" => Ok(ColorSpace::Lch),
+            "oklab" => Ok(ColorSpace::Oklab),
+            "oklch" => Ok(ColorSpace::Oklch),
             _ => Err(ParseError::ParseColorSpace),
         }
     }
 }
 
+impl Color {
+    /// Returns the names of the variables the color references directly, in order of
+    /// appearance.
+    pub fn variables(&self) -> Vec<&str> {
+        let mut variables = Vec::new();
+        self.collect_variables(&mut variables);
+        variables
+    }
+
+    fn collect_variables<'a>(&'a self, variables: &mut Vec<&'a str>) {
+        match self {
+            Color::Variable(name) => {
+                if !variables.contains(&name.as_str()) {
+                    variables.push(name);
+                }
+            }
+            Color::Expression(color, adjusters) => {
+                color.collect_variables(variables);
+
+                for adjuster in adjusters {
+                    match adjuster {
+                        Adjuster::Blend(color, ..)
+                        | Adjuster::BlendAlpha(color, ..)
+                        | Adjuster::MinContrast(color, _) => color.collect_variables(variables),
+                        _ => {}
+                    }
+                }
+            }
+            Color::Mix(_, first, _, second, _) => {
+                first.collect_variables(variables);
+                second.collect_variables(variables);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Formats a number that must be parsed back as a float, e.g. `1.0` instead of `1`.
+fn format_float(value: f32) -> String {
+    if value.fract() == 0.0 {
+        format!("{value:.1}")
+    } else {
+        value.to_string()
+    }
+}
+
+/// Formats a fraction as a percentage, e.g. `50%` for `0.5`.
+fn format_percentage(value: f32) -> String {
+    // NOTE: Round away float noise such as `7.0000005%`.
+    let percentage = (value * 100.0 * 1000.0).round() / 1000.0;
+    format!("{percentage}%")
+}
+
+/// Formats the alpha of a space separated color function, e.g. ` / 0.5`.
+fn format_alpha(alpha: &Option<f32>) -> String {
+    alpha.map(|alpha| format!(" / {alpha}")).unwrap_or_default()
+}
+
+/// Formats the color in the syntax it is parsed from.
+///
+/// Named colors are written as hex colors, since only their value is kept.
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Color::Hex(hex) | Color::HexAlpha(hex) => write!(f, "#{hex}"),
+            Color::RGB(red, green, blue) => write!(f, "rgb({red}, {green}, {blue})"),
+            Color::RGBA(red, green, blue, alpha) => {
+                write!(f, "rgba({red}, {green}, {blue}, {})", format_float(*alpha))
+            }
+            Color::HSL(hue, saturation, lightness) => write!(
+                f,
+                "hsl({hue}, {}, {})",
+                format_percentage(*saturation),
+                format_percentage(*lightness)
+            ),
+            Color::HSLA(hue, saturation, lightness, alpha) => write!(
+                f,
+                "hsla({hue}, {}, {}, {})",
+                format_percentage(*saturation),
+                format_percentage(*lightness),
+                format_float(*alpha)
+            ),
+            Color::HWB(hue, whiteness, blackness, alpha) => {
+                write!(
+                    f,
+                    "hwb({hue}, {}, {}",
+                    format_percentage(*whiteness),
+                    format_percentage(*blackness)
+                )?;
+
+                if let Some(alpha) = alpha {
+                    write!(f, ", {}", format_float(*alpha))?;
+                }
+
+                write!(f, ")")
+            }
+            Color::Lab(lightness, a, b, alpha) => {
+                write!(f, "lab({lightness} {a} {b}{})", format_alpha(alpha))
+            }
+            Color::Lch(lightness, chroma, hue, alpha) => {
+                write!(f, "lch({lightness} {chroma} {hue}{})", format_alpha(alpha))
+            }
+            Color::Oklab(lightness, a, b, alpha) => {
+                write!(f, "oklab({lightness} {a} {b}{})", format_alpha(alpha))
+            }
+            Color::Oklch(lightness, chroma, hue, alpha) => {
+                write!(
+                    f,
+                    "oklch({lightness} {chroma} {hue}{})",
+                    format_alpha(alpha)
+                )
+            }
+            Color::Named(color) => {
+                write!(f, "#{:02x}{:02x}{:02x}", color.red, color.green, color.blue)
+            }
+            Color::Variable(name) => write!(f, "var({name})"),
+            Color::Expression(color, adjusters) => {
+                write!(f, "color({color}")?;
+
+                for adjuster in adjusters {
+                    write!(f, " {adjuster}")?;
+                }
+
+                write!(f, ")")
+            }
+            Color::Mix(space, first, first_percentage, second, second_percentage) => {
+                let mixed = |color: &Color, percentage: &Option<f32>| match percentage {
+                    Some(percentage) => format!("{color} {}", format_percentage(*percentage)),
+                    None => color.to_string(),
+                };
+
+                // NOTE: CSS names the RGB color space `srgb`, Sublime Text uses `rgb` for blends.
+                let space = match space {
+                    ColorSpace::RGB => "srgb".to_string(),
+                    space => space.to_string(),
+                };
+
+                write!(
+                    f,
+                    "color-mix(in {space}, {}, {})",
+                    mixed(first, first_percentage),
+                    mixed(second, second_percentage)
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for Adjuster {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let relative = |value: f32, relative: bool| {
+            let percentage = format_percentage(value.abs());
+
+            match (relative, value < 0.0) {
+                (false, _) => percentage,
+                (true, false) => format!("+ {percentage}"),
+                (true, true) => format!("- {percentage}"),
+            }
+        };
+
+        let blend = |name: &str, color: &Color, percentage: f32, space: &Option<ColorSpace>| {
+            let space = space.map(|space| format!(" {space}")).unwrap_or_default();
+            format!("{name}({color} {}{space})", format_percentage(percentage))
+        };
+
+        match self {
+            Adjuster::Blend(color, percentage, space) => {
+                write!(f, "{}", blend("blend", color, *percentage, space))
+            }
+            Adjuster::BlendAlpha(color, percentage, space) => {
+                write!(f, "{}", blend("blenda", color, *percentage, space))
+            }
+            Adjuster::Alpha(alpha) => write!(f, "alpha({})", format_float(*alpha)),
+            Adjuster::Saturation(value, is_relative) => {
+                write!(f, "s({})", relative(*value, *is_relative))
+            }
+            Adjuster::Lightness(value, is_relative) => {
+                write!(f, "l({})", relative(*value, *is_relative))
+            }
+            Adjuster::MinContrast(color, ratio) => {
+                write!(f, "min-contrast({color} {})", format_float(*ratio))
+            }
+        }
+    }
+}
+
+impl fmt::Display for ColorSpace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorSpace::RGB => write!(f, "rgb"),
+            ColorSpace::LinearRGB => write!(f, "srgb-linear"),
+            ColorSpace::HSL => write!(f, "hsl"),
+            ColorSpace::HWB => write!(f, "hwb"),
+            ColorSpace::Lab => write!(f, "lab"),
+            ColorSpace::Lch => write!(f, "lch"),
+            ColorSpace::Oklab => write!(f, "oklab"),
+            ColorSpace::Oklch => write!(f, "oklch"),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum Number {
     Integer(i32),
@@ -119,10 +353,63 @@ pub enum Token {
     WhiteSpace,
     CloseParen,
     Comma,
+    Slash,
     Number(String),
     Literal(String),
 }
 
+impl Token {
+    /// Returns the text the token was read from, whitespace as a single space.
+    fn text(&self) -> &str {
+        match self {
+            Token::Hash => "#",
+            Token::OpenParen => "(",
+            Token::AddOperator => "+",
+            Token::SubtractOperator => "-",
+            Token::Point => ".",
+            Token::Percent => "%",
+            Token::WhiteSpace => " ",
+            Token::CloseParen => ")",
+            Token::Comma => ",",
+            Token::Slash => "/",
+            Token::Number(text) | Token::Literal(text) => text.as_str(),
+        }
+    }
+}
+
+/// A component of a color function in the space separated syntax, e.g. `50%` or `120deg`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Component {
+    Number(f32),
+    /// A fraction, e.g. `0.5` for `50%`
+    Percentage(f32),
+    /// An angle in degrees
+    Angle(f32),
+    /// The `none` keyword, a missing component that evaluates to zero
+    Missing,
+}
+
+impl Component {
+    /// Returns the value of the component, with percentages scaled so `100%` is `reference`.
+    fn value(self, reference: f32) -> Result<f32, ParseError> {
+        match self {
+            Component::Number(value) => Ok(value),
+            Component::Percentage(value) => Ok(value * reference),
+            Component::Missing => Ok(0.0),
+            Component::Angle(_) => Err(ParseError::InvalidColorFunction),
+        }
+    }
+
+    /// Returns the component as a hue in degrees between 0 and 360.
+    fn hue(self) -> Result<f32, ParseError> {
+        match self {
+            Component::Number(degrees) | Component::Angle(degrees) => Ok(degrees.rem_euclid(360.0)),
+            Component::Missing => Ok(0.0),
+            Component::Percentage(_) => Err(ParseError::InvalidColorFunction),
+        }
+    }
+}
+
 pub fn parse_color(s: &str) -> Result<Color, ParseError> {
     let mut chars = s.chars();
     let mut stream = get_tokens(&mut chars).peekable();
@@ -141,6 +428,8 @@ pub fn parse_color(s: &str) -> Result<Color, ParseError> {
     for token in stream.by_ref() {
         match token {
             Token::Literal(literal) => name.push_str(&literal),
+            // NOTE: color-mix contains a dash in the name
+            Token::SubtractOperator => name.push('-'),
 
             Token::WhiteSpace => continue,
             Token::OpenParen => {
@@ -177,11 +466,25 @@ pub fn parse_color(s: &str) -> Result<Color, ParseError> {
 
             Ok(Color::Expression(Box::new(color), adjusters))
         }
+        ColorFunction::Mix => parse_color_mix(&function_body(&mut stream)?),
+        ColorFunction::Lab | ColorFunction::Lch | ColorFunction::Oklab | ColorFunction::Oklch => {
+            parse_space_separated(&function_body(&mut stream)?, color_function)
+        }
         ColorFunction::HSL
         | ColorFunction::HSLA
         | ColorFunction::HWB
         | ColorFunction::RGB
-        | ColorFunction::RGBA => parse_color_function(&mut stream, color_function),
+        | ColorFunction::RGBA => {
+            let tokens: Vec<Token> = stream.collect();
+
+            // NOTE: Sublime Text documents the legacy syntax with commas, CSS Color Level 4
+            // separates the arguments with spaces.
+            if tokens.contains(&Token::Comma) {
+                parse_color_function(&mut tokens.into_iter(), color_function)
+            } else {
+                parse_space_separated(&function_body(&mut tokens.into_iter())?, color_function)
+            }
+        }
     }
 }
 
@@ -378,6 +681,7 @@ fn get_tokens(chars: &mut impl Iterator<Item = char>) -> impl Iterator<Item = To
             '-' => tokens.push(Token::SubtractOperator),
             '%' => tokens.push(Token::Percent),
             ',' => tokens.push(Token::Comma),
+            '/' => tokens.push(Token::Slash),
             _ => (),
         }
 
@@ -432,6 +736,8 @@ fn split_expression(stream: &mut impl Iterator<Item = Token>) -> Result<Vec<Stri
                 current_argument.push(')');
             }
             Token::Comma => current_argument.push(','),
+            Token::Hash => current_argument.push('#'),
+            Token::Slash => current_argument.push('/'),
             Token::Point => current_argument.push('.'),
             Token::Percent => current_argument.push('%'),
             Token::AddOperator => current_argument.push('+'),
@@ -464,6 +770,218 @@ fn split_expression(stream: &mut impl Iterator<Item = Token>) -> Result<Vec<Stri
     Ok(arguments)
 }
 
+/// Collects the text of a function up to its closing parenthesis, e.g. `255 0 0 / 50%` for
+/// `rgb(255 0 0 / 50%)`.
+fn function_body(stream: &mut impl Iterator<Item = Token>) -> Result<String, ParseError> {
+    let mut body = String::new();
+    let mut level = 1;
+
+    for token in stream.by_ref() {
+        match token {
+            Token::OpenParen => level += 1,
+            Token::CloseParen => {
+                level -= 1;
+
+                if level == 0 {
+                    return Ok(body);
+                }
+            }
+            _ => {}
+        }
+
+        body.push_str(token.text());
+    }
+
+    Err(ParseError::InvalidColorFunction)
+}
+
+/// Splits the arguments of a function at the commas outside of nested functions.
+fn split_arguments(body: &str) -> Vec<&str> {
+    let mut arguments = Vec::new();
+    let mut level = 0;
+    let mut start = 0;
+
+    for (index, char) in body.char_indices() {
+        match char {
+            '(' => level += 1,
+            ')' => level -= 1,
+            ',' if level == 0 => {
+                arguments.push(body[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+
+    arguments.push(body[start..].trim());
+    arguments
+}
+
+fn parse_component(input: &str) -> Result<Component, ParseError> {
+    let input = input.to_lowercase();
+
+    if input == "none" {
+        return Ok(Component::Missing);
+    }
+
+    if let Some(number) = input.strip_suffix('%') {
+        return Ok(Component::Percentage(number.parse::<f32>()? / 100.0));
+    }
+
+    // NOTE: `grad` has to be checked before `rad`, it ends with it.
+    let units = [
+        ("deg", 1.0),
+        ("grad", 0.9),
+        ("rad", 180.0 / PI),
+        ("turn", 360.0),
+    ];
+
+    for (unit, degrees) in units {
+        if let Some(number) = input.strip_suffix(unit) {
+            return Ok(Component::Angle(number.parse::<f32>()? * degrees));
+        }
+    }
+
+    Ok(Component::Number(input.parse::<f32>()?))
+}
+
+/// Parses the arguments of a color function in the space separated syntax of CSS Color Level 4,
+/// e.g. `120deg 50% 25% / 0.5` for `hsl()`.
+fn parse_space_separated(body: &str, color_function: ColorFunction) -> Result<Color, ParseError> {
+    let (channels, alpha) = match body.split_once('/') {
+        Some((channels, alpha)) => {
+            let alpha = parse_component(alpha.trim())?.value(1.0)?;
+            (channels, Some(alpha.clamp(0.0, 1.0)))
+        }
+        None => (body, None),
+    };
+
+    let channels: Vec<Component> = channels
+        .split_whitespace()
+        .map(parse_component)
+        .collect::<Result<_, _>>()?;
+
+    let &[first, second, third] = channels.as_slice() else {
+        return Err(ParseError::InvalidColorFunction);
+    };
+
+    // NOTE: HSL and HWB accept plain numbers from 0 to 100 as well as percentages.
+    let fraction = |component: Component| {
+        component
+            .value(100.0)
+            .map(|value| (value / 100.0).clamp(0.0, 1.0))
+    };
+    let whole_degrees = |component: Component| component.hue().map(|hue| hue.round() as u32 % 360);
+
+    match color_function {
+        ColorFunction::RGB | ColorFunction::RGBA => {
+            let channel = |component: Component| {
+                component
+                    .value(255.0)
+                    .map(|value| value.round().clamp(0.0, 255.0) as u8)
+            };
+
+            let (red, green, blue) = (channel(first)?, channel(second)?, channel(third)?);
+
+            Ok(match alpha {
+                Some(alpha) => Color::RGBA(red, green, blue, alpha),
+                None => Color::RGB(red, green, blue),
+            })
+        }
+        ColorFunction::HSL | ColorFunction::HSLA => {
+            let (hue, saturation, lightness) =
+                (whole_degrees(first)?, fraction(second)?, fraction(third)?);
+
+            Ok(match alpha {
+                Some(alpha) => Color::HSLA(hue, saturation, lightness, alpha),
+                None => Color::HSL(hue, saturation, lightness),
+            })
+        }
+        ColorFunction::HWB => Ok(Color::HWB(
+            whole_degrees(first)?,
+            fraction(second)?,
+            fraction(third)?,
+            alpha,
+        )),
+        ColorFunction::Lab => Ok(Color::Lab(
+            first.value(100.0)?.clamp(0.0, 100.0),
+            second.value(125.0)?,
+            third.value(125.0)?,
+            alpha,
+        )),
+        ColorFunction::Lch => Ok(Color::Lch(
+            first.value(100.0)?.clamp(0.0, 100.0),
+            second.value(150.0)?.max(0.0),
+            third.hue()?,
+            alpha,
+        )),
+        ColorFunction::Oklab => Ok(Color::Oklab(
+            first.value(1.0)?.clamp(0.0, 1.0),
+            second.value(0.4)?,
+            third.value(0.4)?,
+            alpha,
+        )),
+        ColorFunction::Oklch => Ok(Color::Oklch(
+            first.value(1.0)?.clamp(0.0, 1.0),
+            second.value(0.4)?.max(0.0),
+            third.hue()?,
+            alpha,
+        )),
+        _ => Err(ParseError::InvalidColorFunction),
+    }
+}
+
+/// Parses the arguments of `color-mix()`, e.g. `in oklch, red 40%, blue`.
+fn parse_color_mix(body: &str) -> Result<Color, ParseError> {
+    let &[space, first, second] = split_arguments(body).as_slice() else {
+        return Err(ParseError::InvalidColorFunction);
+    };
+
+    let space = match space.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["in", space] => ColorSpace::from_str(space)?,
+        _ => return Err(ParseError::ParseColorSpace),
+    };
+
+    let (first, first_percentage) = parse_mix_color(first)?;
+    let (second, second_percentage) = parse_mix_color(second)?;
+
+    if let (Some(first), Some(second)) = (first_percentage, second_percentage) {
+        if first + second <= 0.0 {
+            return Err(ParseError::InvalidColorFunction);
+        }
+    }
+
+    Ok(Color::Mix(
+        space,
+        Box::new(first),
+        first_percentage,
+        Box::new(second),
+        second_percentage,
+    ))
+}
+
+/// Parses a color of `color-mix()` with its optional percentage, e.g. `red 40%` or `40% red`.
+fn parse_mix_color(input: &str) -> Result<(Color, Option<f32>), ParseError> {
+    let percentage = |input: &str| match parse_number_string(input)? {
+        Number::Float(percentage) if (0.0..=1.0).contains(&percentage) => Ok(percentage),
+        _ => Err(ParseError::InvalidColorFunction),
+    };
+
+    if let Some((color, last)) = input.rsplit_once(char::is_whitespace) {
+        if last.ends_with('%') {
+            return Ok((parse_color(color)?, Some(percentage(last)?)));
+        }
+    }
+
+    if let Some((first, color)) = input.split_once(char::is_whitespace) {
+        if first.ends_with('%') {
+            return Ok((parse_color(color)?, Some(percentage(first)?)));
+        }
+    }
+
+    Ok((parse_color(input)?, None))
+}
+
 fn parse_number(stream: &mut impl Iterator<Item = Token>) -> Result<Number, ParseError> {
     let mut chars = String::new();
     for token in stream.by_ref() {
@@ -619,7 +1137,7 @@ fn parse_color_function(
         }
 
         ColorFunction::HWB => {
-            if numbers.len() != 3 || numbers.len() != 4 {
+            if numbers.len() != 3 && numbers.len() != 4 {
                 return Err(ParseError::InvalidColorFunction);
             }
 
@@ -652,7 +1170,7 @@ fn parse_color_function(
                 hue,
                 arguments[0],
                 arguments[1],
-                Some(arguments[2]),
+                arguments.get(2).copied(),
             ))
         }
 
@@ -746,6 +1264,11 @@ fn parse_hex(stream: &mut impl Iterator<Item = Token>) -> Result<Color, ParseErr
         }
     }
 
+    // NOTE: Literals include any alphabetic character, the color is sliced by bytes later.
+    if !code.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ParseError::InvalidHexColor);
+    }
+
     info!("Successfully parsed hex color: {}", code);
     match code.len() {
         3 => Ok(Color::Hex(code)),
@@ -881,6 +1404,88 @@ mod tests {
         }
     }
 
+    #[test]
+    fn display_colors() {
+        start_log();
+
+        let colors = [
+            "#9900ee",
+            "rgba(10, 20, 30, 1.0)",
+            "hsl(120, 50%, 25%)",
+            "hwb(0, 10%, 20%, 0.5)",
+            "var(blue)",
+            "color(var(blue) blend(#fff 20%) alpha(0.8))",
+            "color(#000 l(+ 10%) s(- 5%) blenda(hsl(10, 20%, 30%) 40% hsl))",
+            "color(var(fg) min-contrast(var(bg) 4.5))",
+        ];
+
+        for string in colors {
+            let color = parse_color(string).unwrap();
+
+            assert_eq!(color.to_string(), string);
+            assert_eq!(parse_color(&color.to_string()).unwrap(), color);
+        }
+
+        assert_eq!(
+            parse_color("color(var(fg) min-contrast(var(bg) 4.5) blend(var(fg) 10%))")
+                .unwrap()
+                .variables(),
+            vec!["fg", "bg"]
+        );
+    }
+
+    #[test]
+    fn parse_space_separated_colors() {
+        start_log();
+
+        let colors = [
+            ("rgb(255 0 0 / 50%)", Color::RGBA(255, 0, 0, 0.5)),
+            ("rgb(100% 50% 0%)", Color::RGB(255, 128, 0)),
+            ("hsl(0.5turn 100% 50%)", Color::HSL(180, 1.0, 0.5)),
+            (
+                "hsla(120deg 100 25 / 0.5)",
+                Color::HSLA(120, 1.0, 0.25, 0.5),
+            ),
+            ("hwb(200grad 20% 30%)", Color::HWB(180, 0.2, 0.3, None)),
+            ("lab(50% 40 -20%)", Color::Lab(50.0, 40.0, -25.0, None)),
+            (
+                "lch(52.2 72.2 -30deg / none)",
+                Color::Lch(52.2, 72.2, 330.0, Some(0.0)),
+            ),
+            (
+                "oklab(50% 0.225 0.126)",
+                Color::Oklab(0.5, 0.225, 0.126, None),
+            ),
+            (
+                "oklch(0.7 50% 200 / 25%)",
+                Color::Oklch(0.7, 0.2, 200.0, Some(0.25)),
+            ),
+            (
+                "color-mix(in oklch, #ff0000 40%, var(blue))",
+                Color::Mix(
+                    ColorSpace::Oklch,
+                    Box::new(Color::Hex("ff0000".to_string())),
+                    Some(0.4),
+                    Box::new(Color::Variable("blue".to_string())),
+                    None,
+                ),
+            ),
+        ];
+
+        for (string, expected) in colors {
+            let color = parse_color(string).unwrap();
+
+            assert_eq!(color, expected);
+            assert_eq!(parse_color(&color.to_string()).unwrap(), color);
+        }
+
+        assert!(parse_color("rgb(255 0)").is_err());
+        assert!(parse_color("lab(50, 40, 20)").is_err());
+        assert!(parse_color("hsl(120% 50% 50%)").is_err());
+        assert!(parse_color("color-mix(in srgb, red 0%, blue 0%)").is_err());
+        assert!(parse_color("color-mix(in cmyk, red, blue)").is_err());
+    }
+
     #[test]
     fn invalid_hex() {
         assert!(parse_color("#8800-0").is_err());
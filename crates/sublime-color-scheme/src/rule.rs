@@ -0,0 +1,262 @@
+//! [Rules](https://www.sublimetext.com/docs/color_schemes.html#rules) of a color scheme.
+
+use std::{collections::HashMap, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use syntect::highlighting::{Color as SyntectColor, FontStyle, ScopeSelectors};
+
+use crate::{color::VariableResolver, error::ParseError};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Rule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub scope: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_style: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub foreground: Option<RuleColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<RuleColor>,
+    /// Adjusters applied to the foreground of tokens when the rule sets a background,
+    /// e.g. `l(+ 10%)`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub foreground_adjust: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selection_foreground: Option<String>,
+}
+
+/// Color of a rule, either a single color or several colors.
+///
+/// Several foreground colors enable
+/// [hashed syntax highlighting](https://www.sublimetext.com/docs/color_schemes.html#hashed-syntax-highlighting),
+/// several background colors form a gradient.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum RuleColor {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl RuleColor {
+    /// Returns the colors, a single color is returned as a list of one.
+    pub fn colors(&self) -> Vec<&str> {
+        match self {
+            RuleColor::Single(color) => vec![color.as_str()],
+            RuleColor::Multiple(colors) => colors.iter().map(String::as_str).collect(),
+        }
+    }
+
+    /// Returns the first color, the one used when only a single color is supported.
+    pub fn first(&self) -> Option<&str> {
+        self.colors().first().copied()
+    }
+}
+
+/// Font styles of a rule, parsed from a space separated list such as `bold italic`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RuleFontStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub glow: bool,
+    pub underline: bool,
+    pub stippled_underline: bool,
+    pub squiggly_underline: bool,
+}
+
+impl FromStr for RuleFontStyle {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut style = RuleFontStyle::default();
+
+        for word in s.split_whitespace() {
+            match word {
+                "bold" => style.bold = true,
+                "italic" => style.italic = true,
+                "glow" => style.glow = true,
+                "underline" => style.underline = true,
+                "stippled_underline" => style.stippled_underline = true,
+                "squiggly_underline" => style.squiggly_underline = true,
+                // NOTE: Older schemes use "regular" or "normal" to reset inherited styles.
+                "regular" | "normal" => {}
+                _ => return Err(ParseError::InvalidFontStyle(word.to_string())),
+            }
+        }
+
+        Ok(style)
+    }
+}
+
+impl From<RuleFontStyle> for FontStyle {
+    /// Syntect has no glow and only a single kind of underline, so glow is dropped and every
+    /// underline becomes [FontStyle::UNDERLINE].
+    fn from(style: RuleFontStyle) -> Self {
+        let mut font_style = FontStyle::empty();
+
+        if style.bold {
+            font_style |= FontStyle::BOLD;
+        }
+
+        if style.italic {
+            font_style |= FontStyle::ITALIC;
+        }
+
+        if style.underline || style.stippled_underline || style.squiggly_underline {
+            font_style |= FontStyle::UNDERLINE;
+        }
+
+        font_style
+    }
+}
+
+/// A [Rule] with its scope and colors resolved.
+///
+/// Unlike a [syntect::highlighting::ThemeItem], it keeps every color of hashed foregrounds and
+/// background gradients, the selection foreground and all font styles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyleRule {
+    pub name: Option<String>,
+    pub scope: ScopeSelectors,
+    /// Colors the foreground is hashed between, a single color for regular rules
+    pub foreground: Vec<SyntectColor>,
+    /// Colors of the background gradient, a single color for a solid background
+    pub background: Vec<SyntectColor>,
+    pub foreground_adjust: Option<String>,
+    pub selection_foreground: Option<SyntectColor>,
+    pub font_style: Option<RuleFontStyle>,
+}
+
+impl Rule {
+    /// Parses the font style of the rule.
+    pub fn font_style(&self) -> Result<Option<RuleFontStyle>, ParseError> {
+        self.font_style
+            .as_deref()
+            .map(RuleFontStyle::from_str)
+            .transpose()
+    }
+
+    /// Lists the features of the rule that are lost when converting to a
+    /// [syntect::highlighting::Theme].
+    pub fn unsupported_features(&self) -> Vec<&'static str> {
+        let multiple = |color: &Option<RuleColor>| matches!(color, Some(RuleColor::Multiple(_)));
+        let font_style = self.font_style().ok().flatten().unwrap_or_default();
+
+        [
+            (multiple(&self.foreground), "hashed foreground"),
+            (multiple(&self.background), "background gradient"),
+            (self.foreground_adjust.is_some(), "foreground_adjust"),
+            (self.selection_foreground.is_some(), "selection_foreground"),
+            (font_style.glow, "glow"),
+            (
+                font_style.stippled_underline || font_style.squiggly_underline,
+                "stippled or squiggly underline",
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(used, feature)| used.then_some(feature))
+        .collect()
+    }
+
+    /// Resolves the scope and colors of the rule.
+    pub fn resolve(&self, variables: &HashMap<String, String>) -> Result<StyleRule, ParseError> {
+        self.resolve_with(&VariableResolver::new(variables), &|_, _, err| err)
+    }
+
+    /// Resolves the rule, passing errors through `locate` with the property and input they
+    /// occurred in, e.g. `foreground[1]`.
+    pub(crate) fn resolve_with(
+        &self,
+        resolver: &VariableResolver,
+        locate: &dyn Fn(String, &str, ParseError) -> ParseError,
+    ) -> Result<StyleRule, ParseError> {
+        let resolve = |property: &str, color: &Option<RuleColor>| {
+            let Some(color) = color else {
+                return Ok(Vec::new());
+            };
+
+            color
+                .colors()
+                .into_iter()
+                .enumerate()
+                .map(|(index, input)| {
+                    resolver.color(input).map_err(|err| {
+                        let property = match color {
+                            RuleColor::Single(_) => property.to_string(),
+                            RuleColor::Multiple(_) => format!("{property}[{index}]"),
+                        };
+
+                        locate(property, input, err)
+                    })
+                })
+                .collect::<Result<Vec<_>, ParseError>>()
+        };
+
+        Ok(StyleRule {
+            name: self.name.clone(),
+            scope: ScopeSelectors::from_str(&self.scope)
+                .map_err(|err| locate("scope".to_string(), &self.scope, err.into()))?,
+            foreground: resolve("foreground", &self.foreground)?,
+            background: resolve("background", &self.background)?,
+            foreground_adjust: self.foreground_adjust.clone(),
+            selection_foreground: self
+                .selection_foreground
+                .as_deref()
+                .map(|input| {
+                    resolver
+                        .color(input)
+                        .map_err(|err| locate("selection_foreground".to_string(), input, err))
+                })
+                .transpose()?,
+            font_style: self
+                .font_style
+                .as_deref()
+                .map(|input| {
+                    resolver
+                        .text(input)
+                        .and_then(|style| RuleFontStyle::from_str(&style))
+                        .map_err(|err| locate("font_style".to_string(), input, err))
+                })
+                .transpose()?,
+        })
+    }
+}
+
+impl StyleRule {
+    /// Returns the foreground color of `token`.
+    ///
+    /// With several colors, the token is hashed to a position on the gradient between them, so
+    /// the same token always gets the same color.
+    pub fn foreground_for(&self, token: &str) -> Option<SyntectColor> {
+        match self.foreground.len() {
+            0 => None,
+            1 => self.foreground.first().copied(),
+            len => {
+                // NOTE: FNV-1a, unlike the std hashers it is stable across Rust versions.
+                let hash = token.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+                    (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+                });
+
+                let position = (hash % 1000) as f32 / 999.0 * (len - 1) as f32;
+                let index = (position.floor() as usize).min(len - 2);
+
+                Some(interpolate(
+                    self.foreground[index],
+                    self.foreground[index + 1],
+                    position - index as f32,
+                ))
+            }
+        }
+    }
+}
+
+fn interpolate(from: SyntectColor, to: SyntectColor, amount: f32) -> SyntectColor {
+    let channel =
+        |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * amount).round() as u8;
+
+    SyntectColor {
+        r: channel(from.r, to.r),
+        g: channel(from.g, to.g),
+        b: channel(from.b, to.b),
+        a: channel(from.a, to.a),
+    }
+}
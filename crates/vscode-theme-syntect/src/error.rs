@@ -1,5 +1,8 @@
 //! Errors for parsing Visual Studio Code themes
 
+use std::path::PathBuf;
+
+pub use sublime_color_scheme::error::Location;
 use syntect::{highlighting::ParseThemeError, parsing::ParseScopeError};
 use thiserror::Error;
 
@@ -12,6 +15,20 @@ pub enum ParseError {
     Json(#[from] serde_json::Error),
     #[error("Failed to parse JSONC")]
     Jsonc(#[from] jsonc_parser::errors::ParseError),
+    #[error("Failed to include {}", .path.display())]
+    Include {
+        path: PathBuf,
+        source: Box<ParseError>,
+    },
+    #[error("{} includes itself", .0.display())]
+    IncludeCycle(PathBuf),
+    #[error("Failed to load token colors from {}", .path.display())]
+    TokenColors {
+        path: PathBuf,
+        source: Box<ParseError>,
+    },
+    #[error("Failed to parse tmTheme")]
+    TmTheme(#[from] syntect::LoadingError),
 
     // Color Parsing Errors
     #[error("Failed to parse hex color")]
@@ -49,3 +66,14 @@ pub enum ParseError {
     #[error("Failed to parse theme")]
     ParseTheme(#[from] ParseThemeError),
 }
+
+impl ParseError {
+    /// Returns where the error occurred in the parsed text, if it is known.
+    pub fn location(&self) -> Option<Location> {
+        match self {
+            ParseError::Jsonc(err) => Some(Location::from(err)),
+            ParseError::Json(err) => Location::of_json_error(err),
+            _ => None,
+        }
+    }
+}
@@ -16,18 +16,28 @@
 //!
 //! - [`parse_vscode_theme_file`](parse_vscode_theme_file)
 //! - [`parse_vscode_theme`](parse_vscode_theme)
+//! - [`parse_vscode_theme_with_resolver`](parse_vscode_theme_with_resolver)
+//!
+//! Only the functions that take a path follow `include` and load token colors from files, since
+//! those are found relative to the theme.
 
 use log::debug;
-use serde::Deserialize;
-use std::{collections::HashMap, path::Path, str::FromStr};
+use serde::{
+    de::{self, value::SeqAccessDeserializer, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use std::{collections::HashMap, fmt, path::Path, str::FromStr};
 use syntect::highlighting::{
     Color, FontStyle, ScopeSelectors, StyleModifier, Theme, ThemeItem, ThemeSettings,
 };
 
 pub mod error;
 mod named_color;
+mod resolve;
+mod semantic;
 
 use crate::error::ParseError;
+pub use semantic::{fallback_scope, SemanticTokenColor, SemanticTokenStyle};
 
 /// A token color
 #[derive(Debug, Deserialize)]
@@ -52,38 +62,122 @@ pub struct TokenSettings {
     pub font_style: Option<String>,
 }
 
+/// A rule of the token colors of a theme.
+#[derive(Debug)]
+pub enum TokenRule {
+    Color(TokenColor),
+    /// A rule of a tmTheme the token colors are loaded from, converted without going through a
+    /// [TokenColor]
+    TmTheme(ThemeItem),
+}
+
+/// Token colors of a theme, the rules themselves or the file they are in.
+#[derive(Debug)]
+pub enum TokenColors {
+    /// The rules in the order they apply, later rules take precedence over earlier ones with
+    /// equally specific scopes
+    Rules(Vec<TokenRule>),
+    /// Path of a tmTheme or of a JSON file with the rules, relative to the theme's file
+    Path(String),
+}
+
+impl Default for TokenColors {
+    fn default() -> Self {
+        TokenColors::Rules(Vec::new())
+    }
+}
+
+impl TokenColors {
+    /// Returns the rules, none if they are in a file that isn't loaded.
+    pub fn rules(&self) -> &[TokenRule] {
+        match self {
+            TokenColors::Rules(rules) => rules,
+            TokenColors::Path(_) => &[],
+        }
+    }
+
+    pub fn into_rules(self) -> Vec<TokenRule> {
+        match self {
+            TokenColors::Rules(rules) => rules,
+            TokenColors::Path(_) => Vec::new(),
+        }
+    }
+}
+
+// NOTE: An untagged enum only reports that the value matches no variant, the rules are
+// deserialized directly so the error of an invalid rule is kept.
+impl<'de> Deserialize<'de> for TokenColors {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TokenColorsVisitor;
+
+        impl<'de> Visitor<'de> for TokenColorsVisitor {
+            type Value = TokenColors;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an array of token colors or the path of a file with them")
+            }
+
+            fn visit_str<E: de::Error>(self, path: &str) -> Result<Self::Value, E> {
+                Ok(TokenColors::Path(path.to_string()))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+                let rules: Vec<TokenColor> = Vec::deserialize(SeqAccessDeserializer::new(seq))?;
+
+                Ok(TokenColors::Rules(
+                    rules.into_iter().map(TokenRule::Color).collect(),
+                ))
+            }
+        }
+
+        deserializer.deserialize_any(TokenColorsVisitor)
+    }
+}
+
 /// A Visual Studio Code theme
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct VscodeTheme {
     pub name: Option<String>,
     pub author: Option<String>,
     pub maintainers: Option<Vec<String>>,
     pub type_: Option<String>,
+    /// Path of the theme this theme extends, relative to this theme's file
+    pub include: Option<String>,
+    #[serde(default)]
     pub colors: HashMap<String, Option<String>>,
-    pub token_colors: Vec<TokenColor>,
+    #[serde(default)]
+    pub token_colors: TokenColors,
+    /// Whether the semantic token colors are applied, off unless the theme turns it on like in
+    /// Visual Studio Code
+    pub semantic_highlighting: Option<bool>,
+    /// Styles of semantic token selectors, e.g. `variable.readonly`
+    #[serde(default)]
+    pub semantic_token_colors: HashMap<String, SemanticTokenColor>,
 }
 
 impl FromStr for VscodeTheme {
     type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        // NOTE: Need to remove comments and remove leading commas since JSON doesn't support them.
-        // TODO: Possibly do this manually to get rid of jsonc_parser.
-        let value: serde_json::Value = jsonc_parser::parse_to_ast(
-            s,
-            &jsonc_parser::CollectOptions {
-                comments: jsonc_parser::CommentCollectionStrategy::Off,
-                tokens: false,
-            },
-            &jsonc_parser::ParseOptions::default(),
-        )?
-        .value
-        .into();
-
-        serde_json::from_value(value).map_err(ParseError::Json)
+        serde_json::from_value(parse_jsonc(s)?).map_err(ParseError::Json)
     }
 }
 
+fn parse_jsonc(s: &str) -> Result<serde_json::Value, ParseError> {
+    // NOTE: Need to remove comments and remove leading commas since JSON doesn't support them.
+    // TODO: Possibly do this manually to get rid of jsonc_parser.
+    Ok(jsonc_parser::parse_to_ast(
+        s,
+        &jsonc_parser::CollectOptions {
+            comments: jsonc_parser::CommentCollectionStrategy::Off,
+            tokens: false,
+        },
+        &jsonc_parser::ParseOptions::default(),
+    )?
+    .value
+    .into())
+}
+
 fn get_color(s: &str) -> Result<Color, ParseError> {
     debug!("get_color: {}", s);
     if let Some(color) = named_color::from_name(s) {
@@ -93,10 +187,154 @@ fn get_color(s: &str) -> Result<Color, ParseError> {
     }
 }
 
+impl VscodeTheme {
+    /// Extends `base`, the theme this theme includes.
+    ///
+    /// Like in Visual Studio Code, colors and semantic token colors of this theme replace the
+    /// ones of `base`, and its token colors come after the ones of `base` so they take
+    /// precedence. The name is not inherited, it names the included theme.
+    pub fn extend(self, base: VscodeTheme) -> VscodeTheme {
+        let mut colors = base.colors;
+        colors.extend(self.colors);
+
+        let mut token_colors = base.token_colors.into_rules();
+        token_colors.extend(self.token_colors.into_rules());
+
+        let mut semantic_token_colors = base.semantic_token_colors;
+        semantic_token_colors.extend(self.semantic_token_colors);
+
+        VscodeTheme {
+            name: self.name,
+            author: self.author.or(base.author),
+            maintainers: self.maintainers.or(base.maintainers),
+            type_: self.type_.or(base.type_),
+            include: base.include,
+            colors,
+            token_colors: TokenColors::Rules(token_colors),
+            semantic_highlighting: self.semantic_highlighting.or(base.semantic_highlighting),
+            semantic_token_colors,
+        }
+    }
+
+    /// Converts the semantic token colors to items of the TextMate scopes they fall back to,
+    /// ordered by selector.
+    ///
+    /// Selectors without a TextMate equivalent are skipped, and so are all semantic token colors
+    /// unless the theme turns semantic highlighting on.
+    pub fn semantic_items(&self) -> Vec<ThemeItem> {
+        if self.semantic_highlighting != Some(true) {
+            return Vec::new();
+        }
+
+        let mut selectors: Vec<_> = self.semantic_token_colors.iter().collect();
+        selectors.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        selectors
+            .into_iter()
+            .filter_map(|(selector, color)| {
+                let scope = ScopeSelectors::from_str(&fallback_scope(selector)?).ok()?;
+                let style = color.style();
+
+                Some(ThemeItem {
+                    scope,
+                    style: StyleModifier {
+                        foreground: style.foreground.and_then(|s| get_color(&s).ok()),
+                        background: style.background.and_then(|s| get_color(&s).ok()),
+                        font_style: style.font_style(),
+                    },
+                })
+            })
+            .collect()
+    }
+
+    /// Describes the colors that are dropped when converting to a [Theme] because they fail to
+    /// parse.
+    pub fn warnings(&self) -> Vec<String> {
+        let colors = self
+            .colors
+            .iter()
+            .filter_map(|(key, value)| Some((format!("colors.{key}"), value.as_ref()?)));
+
+        let token_colors = self
+            .token_colors
+            .rules()
+            .iter()
+            .enumerate()
+            .filter_map(|(index, rule)| match rule {
+                TokenRule::Color(color) => Some((index, color)),
+                TokenRule::TmTheme(_) => None,
+            })
+            .flat_map(|(index, color)| {
+                [
+                    ("foreground", &color.settings.foreground),
+                    ("background", &color.settings.background),
+                ]
+                .into_iter()
+                .filter_map(move |(key, value)| {
+                    Some((format!("tokenColors[{index}].{key}"), value.as_ref()?))
+                })
+            });
+
+        let semantic_highlighting = self.semantic_highlighting == Some(true);
+        let semantic_token_colors = self
+            .semantic_token_colors
+            .iter()
+            .filter(|(selector, _)| semantic_highlighting && fallback_scope(selector).is_some())
+            .flat_map(|(selector, color)| {
+                let style = color.style();
+
+                [
+                    ("foreground", style.foreground),
+                    ("background", style.background),
+                ]
+                .into_iter()
+                .filter_map(move |(key, value)| {
+                    Some((format!("semanticTokenColors.{selector}.{key}"), value?))
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut warnings: Vec<String> = colors
+            .chain(token_colors)
+            .chain(
+                semantic_token_colors
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value)),
+            )
+            .filter_map(|(key, value)| {
+                get_color(value)
+                    .err()
+                    .map(|err| format!("Ignoring {key} = \"{value}\": {err}"))
+            })
+            .collect();
+
+        if let TokenColors::Path(path) = &self.token_colors {
+            warnings.push(format!(
+                "Ignoring tokenColors = \"{path}\": the file is not loaded"
+            ));
+        }
+
+        if semantic_highlighting {
+            warnings.extend(
+                self.semantic_token_colors
+                    .keys()
+                    .filter(|selector| fallback_scope(selector).is_none())
+                    .map(|selector| {
+                        format!("Ignoring semanticTokenColors.{selector}: no TextMate scope")
+                    }),
+            );
+        }
+
+        warnings.sort();
+        warnings
+    }
+}
+
 impl TryFrom<VscodeTheme> for Theme {
     type Error = ParseError;
     fn try_from(value: VscodeTheme) -> Result<Self, Self::Error> {
         let mut settings = ThemeSettings::default();
+        let semantic_items = value.semantic_items();
 
         for (key, value) in &value.colors {
             if value.is_none() {
@@ -145,43 +383,56 @@ impl TryFrom<VscodeTheme> for Theme {
             }
         }
 
+        let token_items = value
+            .token_colors
+            .rules()
+            .iter()
+            .map(|rule| {
+                let color = match rule {
+                    TokenRule::Color(color) => color,
+                    TokenRule::TmTheme(item) => return Ok(item.clone()),
+                };
+
+                Ok(ThemeItem {
+                    scope: if let Some(scope) = &color.scope {
+                        match scope {
+                            Scope::Single(s) => ScopeSelectors::from_str(s)?,
+                            Scope::Multiple(s) => ScopeSelectors::from_str(&s.join(","))?,
+                        }
+                    } else {
+                        ScopeSelectors::from_str("*")?
+                    },
+                    style: StyleModifier {
+                        foreground: color
+                            .settings
+                            .foreground
+                            .clone()
+                            .and_then(|s| get_color(&s).ok()),
+                        background: color
+                            .settings
+                            .background
+                            .clone()
+                            .and_then(|s| get_color(&s).ok()),
+                        font_style: color
+                            .settings
+                            .font_style
+                            .clone()
+                            .map(|s| FontStyle::from_str(&s))
+                            .transpose()?,
+                    },
+                })
+            })
+            .collect::<Result<Vec<_>, ParseError>>()?;
+
         Ok(Self {
             name: value.name,
             author: value.author,
-            scopes: value
-                .token_colors
-                .iter()
-                .map(|color| {
-                    Ok(ThemeItem {
-                        scope: if let Some(scope) = &color.scope {
-                            match scope {
-                                Scope::Single(s) => ScopeSelectors::from_str(s)?,
-                                Scope::Multiple(s) => ScopeSelectors::from_str(&s.join(","))?,
-                            }
-                        } else {
-                            ScopeSelectors::from_str("*")?
-                        },
-                        style: StyleModifier {
-                            foreground: color
-                                .settings
-                                .foreground
-                                .clone()
-                                .and_then(|s| get_color(&s).ok()),
-                            background: color
-                                .settings
-                                .background
-                                .clone()
-                                .and_then(|s| get_color(&s).ok()),
-                            font_style: color
-                                .settings
-                                .font_style
-                                .clone()
-                                .map(|s| FontStyle::from_str(&s))
-                                .transpose()?,
-                        },
-                    })
-                })
-                .collect::<Result<Vec<_>, ParseError>>()?,
+            scopes: token_items
+                .into_iter()
+                // NOTE: Syntect picks the most specific selector regardless of order, so semantic
+                // token colors only win over token colors with less specific scopes.
+                .chain(semantic_items)
+                .collect(),
             settings,
         })
     }
@@ -207,7 +458,8 @@ pub fn parse_vscode_theme(scheme: &str) -> Result<VscodeTheme, ParseError> {
 
 /// Parse a Visual Studio Code theme from a file.
 ///
-/// Equivalent to calling [parse_vscode_theme]
+/// Equivalent to calling [parse_vscode_theme], then extending the theme it includes, if any,
+/// see [VscodeTheme::extend].
 ///
 /// # Usage
 ///
@@ -222,13 +474,51 @@ pub fn parse_vscode_theme(scheme: &str) -> Result<VscodeTheme, ParseError> {
 ///
 /// ```
 pub fn parse_vscode_theme_file(path: &Path) -> Result<VscodeTheme, ParseError> {
-    let scheme = std::fs::read_to_string(path)?;
-    parse_vscode_theme(&scheme)
+    parse_vscode_theme_with_resolver(path, |path| std::fs::read_to_string(path))
+}
+
+/// Parse a Visual Studio Code theme, reading it and the files it refers to with `resolve`.
+///
+/// Like [parse_vscode_theme_file], the theme it includes is extended and token colors given as
+/// the path of a tmTheme or JSON file are loaded, but from wherever `resolve` finds them, e.g.
+/// an extension archive in memory. Files are passed to `resolve` joined to the directory of the
+/// file that refers to them, with `.` and `..` removed.
+///
+/// # Usage
+///
+/// ```rust
+/// use std::{collections::HashMap, path::{Path, PathBuf}};
+/// use vscode_theme_syntect::parse_vscode_theme_with_resolver;
+///
+/// let files = HashMap::from([
+///     (PathBuf::from("themes/dark.json"), r#"{ "include": "./base.json" }"#),
+///     (PathBuf::from("themes/base.json"), r#"{ "name": "Base", "tokenColors": [] }"#),
+/// ]);
+///
+/// let theme = parse_vscode_theme_with_resolver(Path::new("themes/dark.json"), |path| {
+///     files
+///         .get(path)
+///         .map(|content| content.to_string())
+///         .ok_or_else(|| std::io::ErrorKind::NotFound.into())
+/// })
+/// .unwrap();
+///
+/// assert!(theme.name.is_none());
+/// ```
+pub fn parse_vscode_theme_with_resolver<F>(
+    path: &Path,
+    resolve: F,
+) -> Result<VscodeTheme, ParseError>
+where
+    F: Fn(&Path) -> std::io::Result<String>,
+{
+    resolve::parse_theme(path, &resolve, &mut Vec::new())
 }
 
 #[cfg(test)]
 mod tests {
     use log::debug;
+    use std::path::PathBuf;
 
     use super::*;
 
@@ -266,4 +556,278 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn report_invalid_colors() {
+        start_log();
+        let theme = VscodeTheme::from_str(
+            r##"{
+                "colors": { "editor.background": "#12345", "editor.foreground": "#ffffff" },
+                "tokenColors": [{ "scope": "comment", "settings": { "foreground": "nope" } }]
+            }"##,
+        )
+        .expect("Failed to parse theme");
+
+        assert_eq!(theme.warnings().len(), 2);
+        assert!(Theme::try_from(theme).is_ok());
+
+        let error = VscodeTheme::from_str("{\n  \"colors\": tru\n}").unwrap_err();
+        assert_eq!(error.location().map(|location| location.line), Some(2));
+    }
+
+    #[test]
+    fn load_token_colors_from_files() {
+        start_log();
+        let files = HashMap::from([
+            (
+                PathBuf::from("themes/dark.json"),
+                r#"{ "name": "Dark", "tokenColors": "../syntaxes/dark.tmTheme" }"#,
+            ),
+            (
+                PathBuf::from("themes/light.json"),
+                r##"{
+                    "name": "Light",
+                    "include": "../syntaxes/dark.tmTheme",
+                    "tokenColors": "./tokens.json"
+                }"##,
+            ),
+            (
+                PathBuf::from("themes/tokens.json"),
+                r##"[{ "scope": "string", "settings": { "foreground": "#00ff00" } }]"##,
+            ),
+            (
+                PathBuf::from("syntaxes/dark.tmTheme"),
+                r##"<?xml version="1.0" encoding="UTF-8"?>
+                <plist version="1.0">
+                <dict>
+                    <key>settings</key>
+                    <array>
+                        <dict>
+                            <key>settings</key>
+                            <dict>
+                                <key>background</key>
+                                <string>#000000</string>
+                            </dict>
+                        </dict>
+                        <dict>
+                            <key>scope</key>
+                            <string>comment, string - string.quoted</string>
+                            <key>settings</key>
+                            <dict>
+                                <key>foreground</key>
+                                <string>#808080</string>
+                                <key>fontStyle</key>
+                                <string>italic</string>
+                            </dict>
+                        </dict>
+                    </array>
+                </dict>
+                </plist>"##,
+            ),
+        ]);
+
+        let resolve = |path: &Path| -> std::io::Result<String> {
+            files
+                .get(path)
+                .map(|content| content.to_string())
+                .ok_or_else(|| std::io::ErrorKind::NotFound.into())
+        };
+
+        let dark =
+            parse_vscode_theme_with_resolver(Path::new("themes/dark.json"), resolve).unwrap();
+        let [TokenRule::TmTheme(globals), TokenRule::TmTheme(comment)] = dark.token_colors.rules()
+        else {
+            panic!("Expected the rules of the tmTheme");
+        };
+        assert_eq!(globals.style.background, Some(Color::BLACK));
+        assert_eq!(
+            comment.scope,
+            ScopeSelectors::from_str("comment, string - string.quoted").unwrap()
+        );
+        assert_eq!(comment.style.font_style, Some(FontStyle::ITALIC));
+
+        let light =
+            parse_vscode_theme_with_resolver(Path::new("themes/light.json"), resolve).unwrap();
+        assert!(matches!(
+            light.token_colors.rules(),
+            [
+                TokenRule::TmTheme(_),
+                TokenRule::TmTheme(_),
+                TokenRule::Color(_)
+            ]
+        ));
+
+        let theme = Theme::try_from(light).unwrap();
+        assert_eq!(theme.scopes[1].style.font_style, Some(FontStyle::ITALIC));
+
+        let missing = parse_vscode_theme_with_resolver(Path::new("themes/missing.json"), resolve);
+        assert!(matches!(missing, Err(ParseError::OpenFile(_))));
+
+        let unresolved = VscodeTheme::from_str(r#"{ "tokenColors": "dark.tmTheme" }"#).unwrap();
+        assert_eq!(unresolved.warnings().len(), 1);
+
+        let invalid = VscodeTheme::from_str(r#"{ "tokenColors": [{ "scope": "comment" }] }"#);
+        assert!(matches!(
+            invalid,
+            Err(ParseError::Json(err)) if err.to_string().contains("missing field `settings`")
+        ));
+    }
+
+    #[test]
+    fn include_base_theme() {
+        start_log();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        std::fs::create_dir_all(dir.join("themes")).unwrap();
+
+        std::fs::write(
+            dir.join("base.json"),
+            r##"{
+                "name": "Base",
+                "colors": { "editor.background": "#000000", "editor.foreground": "#ffffff" },
+                "tokenColors": [{ "scope": "comment", "settings": { "foreground": "#808080" } }],
+                "semanticHighlighting": true,
+                "semanticTokenColors": { "variable.readonly": "#ff0000" }
+            }"##,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("themes/flat.json"),
+            r##"{
+                "name": "Flat",
+                "include": "../base.json",
+                "colors": { "editor.background": "#111111" },
+                "tokenColors": [{ "scope": "string", "settings": { "foreground": "#00ff00" } }],
+                "semanticTokenColors": {
+                    "function": { "foreground": "#0000ff", "bold": true },
+                    "variable.declaration": "#ffff00"
+                }
+            }"##,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("loop.json"),
+            r#"{ "include": "./themes/../loop.json" }"#,
+        )
+        .unwrap();
+
+        let theme = parse_vscode_theme_file(&dir.join("themes/flat.json")).unwrap();
+        let cycle = parse_vscode_theme_file(&dir.join("loop.json")).unwrap_err();
+
+        assert_eq!(theme.name.as_deref(), Some("Flat"));
+        assert_eq!(theme.token_colors.rules().len(), 2);
+        assert_eq!(theme.semantic_token_colors.len(), 3);
+        assert_eq!(
+            theme.warnings(),
+            ["Ignoring semanticTokenColors.variable.declaration: no TextMate scope"]
+        );
+
+        let theme = Theme::try_from(theme).unwrap();
+        assert_eq!(
+            theme.settings.background,
+            Some(Color::from_str("#111111").unwrap())
+        );
+        assert_eq!(theme.settings.foreground, Some(Color::WHITE));
+        assert_eq!(theme.scopes.len(), 4);
+        assert_eq!(theme.scopes[2].style.font_style, Some(FontStyle::BOLD));
+        assert_eq!(
+            theme.scopes[3].style.foreground,
+            Some(Color::from_str("#ff0000").unwrap())
+        );
+
+        assert!(matches!(
+            &cycle,
+            ParseError::Include { source, .. } if matches!(**source, ParseError::IncludeCycle(_))
+        ));
+
+        let plain = VscodeTheme::from_str(
+            r##"{
+                "semanticTokenColors": { "function": "#0000ff", "variable.declaration": "#ffff00" }
+            }"##,
+        )
+        .unwrap();
+        assert!(plain.semantic_items().is_empty());
+        assert!(plain.warnings().is_empty());
+    }
+
+    #[test]
+    fn include_base_theme_with_tm_theme() {
+        start_log();
+        let files = HashMap::from([
+            (
+                PathBuf::from("themes/base.json"),
+                r##"{
+                    "tokenColors": [{ "scope": "comment", "settings": { "foreground": "#00ff00" } }]
+                }"##,
+            ),
+            (
+                PathBuf::from("themes/dark.json"),
+                r#"{ "include": "./base.json", "tokenColors": "./dark.tmTheme" }"#,
+            ),
+            (
+                PathBuf::from("themes/light.json"),
+                r##"{
+                    "include": "./dark.json",
+                    "tokenColors": [{ "scope": "comment", "settings": { "foreground": "#0000ff" } }]
+                }"##,
+            ),
+            (
+                PathBuf::from("themes/dark.tmTheme"),
+                r##"<?xml version="1.0" encoding="UTF-8"?>
+                <plist version="1.0">
+                <dict>
+                    <key>settings</key>
+                    <array>
+                        <dict>
+                            <key>settings</key>
+                            <dict>
+                                <key>background</key>
+                                <string>#000000</string>
+                            </dict>
+                        </dict>
+                        <dict>
+                            <key>scope</key>
+                            <string>comment</string>
+                            <key>settings</key>
+                            <dict>
+                                <key>foreground</key>
+                                <string>#808080</string>
+                            </dict>
+                        </dict>
+                    </array>
+                </dict>
+                </plist>"##,
+            ),
+        ]);
+
+        let resolve = |path: &Path| -> std::io::Result<String> {
+            files
+                .get(path)
+                .map(|content| content.to_string())
+                .ok_or_else(|| std::io::ErrorKind::NotFound.into())
+        };
+
+        // NOTE: Rules of a theme come after the rules of the theme it includes, whether they are
+        // from a tmTheme or not, since the last of equally specific rules wins.
+        let foregrounds = |path: &str| {
+            let theme = parse_vscode_theme_with_resolver(Path::new(path), resolve).unwrap();
+
+            Theme::try_from(theme)
+                .unwrap()
+                .scopes
+                .iter()
+                .map(|item| item.style.foreground)
+                .collect::<Vec<_>>()
+        };
+        let color = |hex| Some(Color::from_str(hex).unwrap());
+
+        assert_eq!(
+            foregrounds("themes/dark.json"),
+            [color("#00ff00"), None, color("#808080")]
+        );
+        assert_eq!(
+            foregrounds("themes/light.json"),
+            [color("#00ff00"), None, color("#808080"), color("#0000ff")]
+        );
+    }
 }
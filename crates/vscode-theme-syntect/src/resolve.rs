@@ -0,0 +1,138 @@
+//! Loading of the files a theme refers to, the theme it includes and its token colors.
+
+use std::{
+    io::Cursor,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+use syntect::highlighting::{ScopeSelectors, StyleModifier, Theme, ThemeItem, ThemeSet};
+
+use crate::{error::ParseError, parse_jsonc, TokenColors, TokenRule, VscodeTheme};
+
+/// Reads a file, see [crate::parse_vscode_theme_with_resolver].
+pub(crate) type Resolve<'a> = &'a dyn Fn(&Path) -> std::io::Result<String>;
+
+/// Parses the theme at `path`, the theme it includes and its token colors, `includes` are the
+/// files that include it.
+pub(crate) fn parse_theme(
+    path: &Path,
+    resolve: Resolve,
+    includes: &mut Vec<PathBuf>,
+) -> Result<VscodeTheme, ParseError> {
+    let path = normalize_path(path);
+
+    if includes.contains(&path) {
+        return Err(ParseError::IncludeCycle(path));
+    }
+
+    // NOTE: Like in Visual Studio Code, included files that aren't JSON are tmThemes.
+    if !is_json(&path) {
+        return Ok(VscodeTheme {
+            token_colors: parse_tm_theme(&resolve(&path)?)?,
+            ..Default::default()
+        });
+    }
+
+    let mut theme: VscodeTheme = resolve(&path)?.parse()?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+
+    if let TokenColors::Path(token_path) = &theme.token_colors {
+        let token_path = normalize_path(&dir.join(token_path));
+        let tokens =
+            parse_token_file(&token_path, resolve).map_err(|err| ParseError::TokenColors {
+                path: token_path,
+                source: Box::new(err),
+            })?;
+
+        theme.token_colors = tokens;
+    }
+
+    let Some(include) = theme.include.take() else {
+        return Ok(theme);
+    };
+
+    let include_path = dir.join(include);
+    includes.push(path.clone());
+
+    let base =
+        parse_theme(&include_path, resolve, includes).map_err(|err| ParseError::Include {
+            path: include_path,
+            source: Box::new(err),
+        })?;
+
+    includes.pop();
+    Ok(theme.extend(base))
+}
+
+/// Parses the token colors in the file at `path`, a tmTheme, a JSON array of token colors or
+/// a theme.
+fn parse_token_file(path: &Path, resolve: Resolve) -> Result<TokenColors, ParseError> {
+    let content = resolve(path)?;
+
+    if !is_json(path) {
+        return parse_tm_theme(&content);
+    }
+
+    let value = parse_jsonc(&content)?;
+
+    let rules = if value.is_array() {
+        serde_json::from_value(value)?
+    } else {
+        serde_json::from_value::<VscodeTheme>(value)?.token_colors
+    };
+
+    Ok(TokenColors::Rules(rules.into_rules()))
+}
+
+/// Parses the rules of a tmTheme, its global foreground and background become a rule matching
+/// every scope like a token color without a scope.
+fn parse_tm_theme(content: &str) -> Result<TokenColors, ParseError> {
+    let theme: Theme = ThemeSet::load_from_reader(&mut Cursor::new(content.as_bytes()))?;
+
+    let globals = ThemeItem {
+        scope: ScopeSelectors::from_str("*")?,
+        style: StyleModifier {
+            foreground: theme.settings.foreground,
+            background: theme.settings.background,
+            font_style: None,
+        },
+    };
+    let has_globals = globals.style.foreground.is_some() || globals.style.background.is_some();
+
+    Ok(TokenColors::Rules(
+        has_globals
+            .then_some(globals)
+            .into_iter()
+            .chain(theme.scopes)
+            .map(TokenRule::TmTheme)
+            .collect(),
+    ))
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "json")
+}
+
+/// Removes `.` and resolves `..` without accessing the file system, so the same file is
+/// recognized when it's included through different relative paths.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
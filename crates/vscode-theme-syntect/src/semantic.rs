@@ -0,0 +1,184 @@
+//! Mapping of [semantic token](https://code.visualstudio.com/api/language-extensions/semantic-highlight-guide)
+//! selectors to TextMate scopes.
+//!
+//! Syntect only highlights TextMate scopes, so semantic token colors are applied to the scopes
+//! Visual Studio Code falls back to when a theme has no semantic token color for a token.
+
+use serde::Deserialize;
+use syntect::highlighting::FontStyle;
+
+/// Scopes of semantic token types and modifiers, from Visual Studio Code's
+/// `tokenClassificationRegistry`.
+const FALLBACK_SCOPES: &[(&str, &[&str])] = &[
+    ("comment", &["comment"]),
+    ("string", &["string"]),
+    ("keyword", &["keyword.control"]),
+    ("number", &["constant.numeric"]),
+    ("regexp", &["constant.regexp"]),
+    ("operator", &["keyword.operator"]),
+    ("namespace", &["entity.name.namespace"]),
+    ("type", &["entity.name.type", "support.type"]),
+    ("struct", &["entity.name.type.struct"]),
+    ("class", &["entity.name.type.class", "support.class"]),
+    ("interface", &["entity.name.type.interface"]),
+    ("enum", &["entity.name.type.enum"]),
+    ("typeParameter", &["entity.name.type.parameter"]),
+    ("function", &["entity.name.function", "support.function"]),
+    (
+        "member",
+        &["entity.name.function.member", "support.function"],
+    ),
+    (
+        "method",
+        &["entity.name.function.member", "support.function"],
+    ),
+    ("macro", &["entity.name.function.preprocessor"]),
+    (
+        "variable",
+        &["variable.other.readwrite", "entity.name.variable"],
+    ),
+    ("parameter", &["variable.parameter"]),
+    ("property", &["variable.other.property"]),
+    ("enumMember", &["variable.other.enummember"]),
+    ("event", &["variable.other.event"]),
+    (
+        "decorator",
+        &["entity.name.decorator", "entity.name.function"],
+    ),
+    ("variable.readonly", &["variable.other.constant"]),
+    ("variable.defaultLibrary", &["support.variable"]),
+    ("variable.readonly.defaultLibrary", &["support.constant"]),
+    ("property.readonly", &["variable.other.constant.property"]),
+    ("property.defaultLibrary", &["support.variable.property"]),
+    (
+        "property.readonly.defaultLibrary",
+        &["support.constant.property"],
+    ),
+    ("type.defaultLibrary", &["support.type"]),
+    ("class.defaultLibrary", &["support.class"]),
+    ("function.defaultLibrary", &["support.function"]),
+];
+
+/// Style of a semantic token, either a foreground color or a full style.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum SemanticTokenColor {
+    Foreground(String),
+    Style(SemanticTokenStyle),
+}
+
+/// The settings of a semantic token
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokenStyle {
+    pub foreground: Option<String>,
+    pub background: Option<String>,
+    pub font_style: Option<String>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underline: Option<bool>,
+    pub strikethrough: Option<bool>,
+}
+
+impl SemanticTokenColor {
+    /// Returns the color as a style, a single color is its foreground.
+    pub fn style(&self) -> SemanticTokenStyle {
+        match self {
+            SemanticTokenColor::Foreground(color) => SemanticTokenStyle {
+                foreground: Some(color.clone()),
+                ..Default::default()
+            },
+            SemanticTokenColor::Style(style) => style.clone(),
+        }
+    }
+}
+
+impl SemanticTokenStyle {
+    /// Combines `fontStyle` with the `bold`, `italic` and `underline` flags, which override it.
+    ///
+    /// Syntect has no strikethrough, so it is dropped.
+    pub fn font_style(&self) -> Option<FontStyle> {
+        let mut font_style = self.font_style.as_deref().map(|font_style| {
+            font_style
+                .split_whitespace()
+                .fold(FontStyle::empty(), |style, word| match word {
+                    "bold" => style | FontStyle::BOLD,
+                    "italic" => style | FontStyle::ITALIC,
+                    "underline" => style | FontStyle::UNDERLINE,
+                    _ => style,
+                })
+        });
+
+        for (flag, value) in [
+            (FontStyle::BOLD, self.bold),
+            (FontStyle::ITALIC, self.italic),
+            (FontStyle::UNDERLINE, self.underline),
+        ] {
+            if let Some(value) = value {
+                font_style
+                    .get_or_insert_with(FontStyle::empty)
+                    .set(flag, value);
+            }
+        }
+
+        font_style
+    }
+}
+
+/// Returns the TextMate scope selector matching the same tokens as the semantic token
+/// `selector`, e.g. `source.rust variable.other.constant` for `variable.readonly:rust`.
+///
+/// Selectors without a TextMate equivalent, such as `*.declaration`, return [None].
+pub fn fallback_scope(selector: &str) -> Option<String> {
+    let (selector, language) = match selector.split_once(':') {
+        Some((selector, language)) => (selector, Some(language)),
+        None => (selector, None),
+    };
+
+    let normalize = |selector: &str| {
+        let mut parts = selector.split('.');
+        let token_type = parts.next().unwrap_or_default();
+        let mut modifiers: Vec<&str> = parts.collect();
+        modifiers.sort_unstable();
+
+        std::iter::once(token_type)
+            .chain(modifiers)
+            .collect::<Vec<_>>()
+            .join(".")
+    };
+
+    let selector = normalize(selector);
+    let (_, scopes) = FALLBACK_SCOPES
+        .iter()
+        .find(|(fallback, _)| normalize(fallback) == selector)?;
+
+    let scopes = scopes.iter().map(|scope| match language {
+        Some(language) => format!("source.{language} {scope}"),
+        None => scope.to_string(),
+    });
+
+    Some(scopes.collect::<Vec<_>>().join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_selectors_to_scopes() {
+        assert_eq!(
+            fallback_scope("function").as_deref(),
+            Some("entity.name.function, support.function")
+        );
+        assert_eq!(
+            fallback_scope("variable.readonly.defaultLibrary").as_deref(),
+            Some("support.constant")
+        );
+        assert_eq!(
+            fallback_scope("variable.readonly:rust").as_deref(),
+            Some("source.rust variable.other.constant")
+        );
+        assert_eq!(fallback_scope("variable.declaration"), None);
+        assert_eq!(fallback_scope("*.defaultLibrary"), None);
+    }
+}